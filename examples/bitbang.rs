@@ -0,0 +1,227 @@
+//! Reads relative humidity and temperature over a bit-banged I²C bus.
+//!
+//! Not every board has a hardware I²C peripheral. The driver works with any
+//! `embedded_hal::i2c::I2c` implementation, including a software one driving two GPIOs.
+//! `bitbang-hal` implements only `embedded-hal` 0.2 traits, therefore a small
+//! bit-banged master is included here.
+//!
+//! During a Hold Master conversion the sensor holds SCL low until the result is ready.
+//! A bit-banged master must wait for SCL to actually go high after releasing it, otherwise
+//! it clocks out bytes before the conversion has finished. The master below does that, but
+//! the example uses [`Si70xx::read_measurements`], which does not rely on clock stretching.
+//!
+//! SDA and SCL are GPIO 2 and 3 on `/dev/gpiochip0`, both require pull-up resistors.
+//!
+//! ```sh
+//! cargo run --example bitbang
+//! ```
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{InputPin, OutputPin},
+    i2c::{ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation},
+};
+use linux_embedded_hal::{
+    gpio_cdev::{Chip, LineRequestFlags},
+    CdevPin, Delay,
+};
+use si70xx::Si70xx;
+
+/// How long the sensor is allowed to stretch the clock in microseconds.
+const CLOCK_STRETCH_TIMEOUT_US: u32 = 50_000;
+
+#[derive(Debug)]
+enum BitbangError {
+    NoAcknowledge,
+    ClockStretchTimeout,
+    Pin,
+}
+
+impl embedded_hal::i2c::Error for BitbangError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            BitbangError::NoAcknowledge => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Unknown),
+            BitbangError::ClockStretchTimeout => ErrorKind::Bus,
+            BitbangError::Pin => ErrorKind::Other,
+        }
+    }
+}
+
+/// I²C master using two open-drain GPIOs, setting a pin high releases the line.
+struct BitbangI2c<SDA, SCL, D> {
+    sda: SDA,
+    scl: SCL,
+    delay: D,
+    half_period_us: u32,
+}
+
+impl<SDA, SCL, D> BitbangI2c<SDA, SCL, D>
+where
+    SDA: InputPin + OutputPin,
+    SCL: InputPin + OutputPin,
+    D: DelayNs,
+{
+    /// Construct new bus running at about 100 kHz.
+    fn new(sda: SDA, scl: SCL, delay: D) -> Self {
+        Self {
+            sda,
+            scl,
+            delay,
+            half_period_us: 5,
+        }
+    }
+
+    fn wait(&mut self) {
+        self.delay.delay_us(self.half_period_us);
+    }
+
+    fn sda(&mut self, high: bool) -> Result<(), BitbangError> {
+        if high {
+            self.sda.set_high().map_err(|_| BitbangError::Pin)
+        } else {
+            self.sda.set_low().map_err(|_| BitbangError::Pin)
+        }
+    }
+
+    fn scl_low(&mut self) -> Result<(), BitbangError> {
+        self.scl.set_low().map_err(|_| BitbangError::Pin)
+    }
+
+    /// Releases SCL and waits until the sensor stops stretching the clock.
+    fn scl_release(&mut self) -> Result<(), BitbangError> {
+        self.scl.set_high().map_err(|_| BitbangError::Pin)?;
+        let mut waited_us = 0;
+        while self.scl.is_low().map_err(|_| BitbangError::Pin)? {
+            if waited_us >= CLOCK_STRETCH_TIMEOUT_US {
+                return Err(BitbangError::ClockStretchTimeout);
+            }
+            self.delay.delay_us(10);
+            waited_us += 10;
+        }
+        Ok(())
+    }
+
+    /// Generates START or repeated START condition.
+    fn start(&mut self) -> Result<(), BitbangError> {
+        self.sda(true)?;
+        self.scl_release()?;
+        self.wait();
+        self.sda(false)?;
+        self.wait();
+        self.scl_low()?;
+        self.wait();
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), BitbangError> {
+        self.sda(false)?;
+        self.wait();
+        self.scl_release()?;
+        self.wait();
+        self.sda(true)?;
+        self.wait();
+        Ok(())
+    }
+
+    fn write_bit(&mut self, bit: bool) -> Result<(), BitbangError> {
+        self.sda(bit)?;
+        self.wait();
+        self.scl_release()?;
+        self.wait();
+        self.scl_low()
+    }
+
+    fn read_bit(&mut self) -> Result<bool, BitbangError> {
+        self.sda(true)?;
+        self.wait();
+        self.scl_release()?;
+        self.wait();
+        let bit = self.sda.is_high().map_err(|_| BitbangError::Pin)?;
+        self.scl_low()?;
+        Ok(bit)
+    }
+
+    fn write_byte(&mut self, byte: u8) -> Result<(), BitbangError> {
+        for i in (0..8).rev() {
+            self.write_bit(byte & (1 << i) != 0)?;
+        }
+        if self.read_bit()? {
+            return Err(BitbangError::NoAcknowledge);
+        }
+        Ok(())
+    }
+
+    fn read_byte(&mut self, ack: bool) -> Result<u8, BitbangError> {
+        let mut byte = 0;
+        for _ in 0..8 {
+            byte = (byte << 1) | self.read_bit()? as u8;
+        }
+        self.write_bit(!ack)?;
+        Ok(byte)
+    }
+
+    fn run(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), BitbangError> {
+        let mut reading = None;
+        for i in 0..operations.len() {
+            let next_is_read = matches!(operations.get(i + 1), Some(Operation::Read(_)));
+            let is_read = matches!(operations[i], Operation::Read(_));
+            if reading != Some(is_read) {
+                self.start()?;
+                self.write_byte((address << 1) | is_read as u8)?;
+                reading = Some(is_read);
+            }
+            match &mut operations[i] {
+                Operation::Write(bytes) => {
+                    for byte in bytes.iter() {
+                        self.write_byte(*byte)?;
+                    }
+                }
+                Operation::Read(buffer) => {
+                    let len = buffer.len();
+                    for (j, byte) in buffer.iter_mut().enumerate() {
+                        // The last byte of a read is not acknowledged.
+                        let last = j + 1 == len && !next_is_read;
+                        *byte = self.read_byte(!last)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<SDA, SCL, D> ErrorType for BitbangI2c<SDA, SCL, D> {
+    type Error = BitbangError;
+}
+
+impl<SDA, SCL, D> I2c for BitbangI2c<SDA, SCL, D>
+where
+    SDA: InputPin + OutputPin,
+    SCL: InputPin + OutputPin,
+    D: DelayNs,
+{
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let result = self.run(address, operations);
+        self.stop()?;
+        result
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut chip = Chip::new("/dev/gpiochip0")?;
+    let flags = LineRequestFlags::OUTPUT | LineRequestFlags::OPEN_DRAIN;
+    let sda = CdevPin::new(chip.get_line(2)?.request(flags.clone(), 1, "si70xx-sda")?)?;
+    let scl = CdevPin::new(chip.get_line(3)?.request(flags, 1, "si70xx-scl")?)?;
+    let i2c = BitbangI2c::new(sda, scl, Delay);
+
+    let mut sensor = Si70xx::new(i2c);
+
+    let m = sensor.read_measurements(&mut Delay).unwrap();
+    println!("Humidity: {:.1}", m.humidity as f32 / 100.);
+    println!("Temperature: {:.1}ºC", m.temperature as f32 / 100.);
+    Ok(())
+}
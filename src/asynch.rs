@@ -0,0 +1,181 @@
+//! Async API, enabled by `async` feature.
+
+use embedded_hal::i2c::{Error as I2cError, ErrorKind};
+use embedded_hal_async::{delay::DelayNs, i2c::I2c};
+
+#[cfg(feature = "heapless")]
+use crate::{crc_valid, Address};
+use crate::{
+    humidity_from_code, humidity_from_code_unclamped, operating_limits, serial_number,
+    temperature_from_code, Async, Characterization, Clock, Command, Device, DeviceConfig,
+    DeviceInfo, Driver, DriverHooks, Error, FirmwareRevision, Frame, HeaterCurrent, Measurement,
+    MeasurementMode, PendingMeasurement, Resolution, ResolutionProfile, ThermistorCoefficients,
+    VddStatus, BENCHMARK_TIMEOUT_MS, DEFAULT_RESOLUTION, HEATER_CONTROL_MASK, MIN_BUFFER_LEN,
+    POLL_INTERVAL_MS, POWER_UP_TIME_MS, READ_ELECTRONIC_ID_1, READ_ELECTRONIC_ID_2,
+    READ_FIRMWARE_REVISION, RESET_TIME_MS, THERMISTOR_CONVERSION_TIME_MS, USER_REGISTER1_HTRE,
+    USER_REGISTER1_RESERVED_MASK, USER_REGISTER1_RES_MASK, USER_REGISTER1_VDDS,
+};
+
+driver_impl!(Async, async, await);
+
+impl<I2C, E, H> Driver<I2C, Async, H>
+where
+    I2C: I2c<Error = E>,
+    H: DriverHooks,
+{
+    /// Measures relative humidity and temperature and resolves when the result is ready.
+    ///
+    /// Starts a No Hold Master measurement and polls the sensor every millisecond using `delay`
+    /// until it acknowledges the read, so that the future can be combined with other events,
+    /// for example using `select`. If the sensor does not respond within the conversion time
+    /// plus margin the last bus error is returned.
+    ///
+    /// Dropping the future while polling leaves the driver usable: the next call first waits for
+    /// the conversion that is still in flight and discards its result.
+    pub async fn await_ready<D: DelayNs>(&mut self, delay: &mut D) -> Result<Measurement, Error<E>>
+    where
+        E: I2cError,
+    {
+        if self.pending.is_some() {
+            let mut discarded = [0u8; 2];
+            self.poll_read(delay, &mut discarded).await?;
+        }
+        self.start_conversion(
+            Command::MeasureRhNoHoldMaster,
+            MeasurementMode::NoHoldMaster,
+        )
+        .await?;
+        let mut response = [0u8; 3];
+        self.poll_read(delay, &mut response[..self.frame_len()])
+            .await?;
+        let humidity = humidity_from_code(self.checked(response, Frame::Humidity)?, self.rounding);
+        let temperature = self.read_temperature().await?;
+        let measurement = Measurement {
+            humidity,
+            temperature,
+        };
+        Ok(self.complete(measurement))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::{AsyncSi705x, AsyncSi70xx, RoundingMode, TemperatureResolution};
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+    use embedded_hal::i2c::NoAcknowledgeSource;
+    use embedded_hal_mock::eh1::delay::NoopDelay;
+    use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+    use std::vec;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    /// Delay that never finishes, for dropping a future while it waits.
+    struct PendingDelay;
+
+    impl DelayNs for PendingDelay {
+        async fn delay_ns(&mut self, _ns: u32) {
+            core::future::pending::<()>().await
+        }
+    }
+
+    fn new_sensor(i2c: I2cMock) -> AsyncSi70xx<I2cMock> {
+        AsyncSi70xx::new(i2c)
+    }
+
+    #[test]
+    fn await_ready_polls_until_acknowledged() {
+        let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0, 0, 0]).with_error(nack),
+            I2cTransaction::read(0x40, vec![0, 0, 0]).with_error(nack),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80, 0xF5]),
+            I2cTransaction::write_read(0x40, vec![0xE0], vec![0x66, 0x4C]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut sensor = new_sensor(i2c.clone());
+        let m = block_on(sensor.await_ready(&mut NoopDelay::new())).unwrap();
+        assert_eq!(
+            m.humidity,
+            humidity_from_code(0x7C80, RoundingMode::Nearest)
+        );
+        assert_eq!(
+            m.temperature,
+            temperature_from_code(0x664C, RoundingMode::Nearest)
+        );
+        i2c.done();
+    }
+
+    #[test]
+    fn await_ready_cancellation_leaves_driver_usable() {
+        let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+        let expectations = [
+            // Cancelled measurement.
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0, 0, 0]).with_error(nack),
+            // Next call finishes in-flight conversion before starting a new one.
+            I2cTransaction::read(0x40, vec![0x11, 0x22]),
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80, 0xF5]),
+            I2cTransaction::write_read(0x40, vec![0xE0], vec![0x66, 0x4C]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut sensor = new_sensor(i2c.clone());
+
+        {
+            let mut delay = PendingDelay;
+            let mut future = pin!(sensor.await_ready(&mut delay));
+            let mut cx = Context::from_waker(Waker::noop());
+            assert!(future.as_mut().poll(&mut cx).is_pending());
+        }
+        assert_eq!(
+            sensor.pending_command(),
+            Some(MeasurementMode::NoHoldMaster)
+        );
+
+        let m = block_on(sensor.await_ready(&mut NoopDelay::new())).unwrap();
+        assert_eq!(
+            m.humidity,
+            humidity_from_code(0x7C80, RoundingMode::Nearest)
+        );
+        assert_eq!(sensor.pending_command(), None);
+        i2c.done();
+    }
+
+    #[test]
+    fn si705x_reads_temperature() {
+        let temperature = [0x66, 0x4C, crate::crc8(&[0x66, 0x4C])];
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]),
+            I2cTransaction::write(0x40, vec![0xE6, 0x3B]),
+            I2cTransaction::write(0x40, vec![0xF3]),
+            I2cTransaction::read(0x40, temperature.to_vec()),
+            I2cTransaction::write_read(0x40, vec![0xE3], vec![0x66, 0x4C, 0x00]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut sensor = AsyncSi705x::new(i2c.clone());
+        block_on(sensor.set_resolution(TemperatureResolution::Bits12)).unwrap();
+        assert_eq!(
+            block_on(sensor.read_temperature(&mut NoopDelay::new())).unwrap(),
+            temperature_from_code(0x664C, RoundingMode::Nearest)
+        );
+        assert!(matches!(
+            block_on(sensor.read_temperature_hold()),
+            Err(Error::Crc(Frame::Temperature))
+        ));
+        i2c.done();
+    }
+}
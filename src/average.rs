@@ -0,0 +1,188 @@
+//! Time-weighted average of measurements over a sliding window.
+
+use crate::{Measurement, RoundingMode};
+
+/// Averages timestamped measurements over a sliding time window.
+///
+/// Measurements are weighted by time, so samples that are close together do not outweigh
+/// samples taken further apart, for example when sampling was skipped during a radio
+/// transmission. Values are interpolated linearly between consecutive samples and the
+/// average is taken from the oldest to the newest sample that is still in the window.
+///
+/// Up to `N` samples are kept. A sample is dropped when it is more than `window_ms` older
+/// than the newest one or when room is needed for a new sample.
+#[derive(Debug, Clone)]
+pub struct WindowAverager<const N: usize> {
+    window_ms: u64,
+    samples: [(u64, Measurement); N],
+    start: usize,
+    len: usize,
+}
+
+impl<const N: usize> WindowAverager<N> {
+    /// Construct new averager over the last `window_ms` milliseconds.
+    pub fn new(window_ms: u64) -> Self {
+        Self {
+            window_ms,
+            samples: [(
+                0,
+                Measurement {
+                    humidity: 0,
+                    temperature: 0,
+                },
+            ); N],
+            start: 0,
+            len: 0,
+        }
+    }
+
+    /// Adds `measurement` taken at `timestamp_ms`.
+    ///
+    /// Timestamps must not decrease, a sample older than the newest one is ignored
+    /// and `false` is returned.
+    pub fn push(&mut self, timestamp_ms: u64, measurement: Measurement) -> bool {
+        if N == 0 {
+            return false;
+        }
+        if let Some((newest, _)) = self.newest() {
+            if timestamp_ms < newest {
+                return false;
+            }
+        }
+        if self.len == N {
+            self.drop_oldest();
+        }
+        self.samples[(self.start + self.len) % N] = (timestamp_ms, measurement);
+        self.len += 1;
+        while self.len > 1 && self.get(0).0 < timestamp_ms.saturating_sub(self.window_ms) {
+            self.drop_oldest();
+        }
+        true
+    }
+
+    /// Returns time-weighted average relative humidity and temperature in the window.
+    ///
+    /// `None` is returned if there are no samples. If all samples have the same timestamp
+    /// their arithmetic mean is returned.
+    pub fn average(&self) -> Option<Measurement> {
+        let (oldest, _) = self.get_checked(0)?;
+        let (newest, _) = self.newest()?;
+        let span = (newest - oldest) as i64;
+        let mut humidity = 0i64;
+        let mut temperature = 0i64;
+        if span == 0 {
+            for i in 0..self.len {
+                let (_, m) = self.get(i);
+                humidity += m.humidity as i64;
+                temperature += m.temperature as i64;
+            }
+            let len = self.len as i64;
+            return Some(Measurement {
+                humidity: RoundingMode::Nearest.divide(humidity, len) as u16,
+                temperature: RoundingMode::Nearest.divide(temperature, len) as i16,
+            });
+        }
+        // Trapezoidal integral, divided by 2 at the end.
+        for i in 1..self.len {
+            let (t0, a) = self.get(i - 1);
+            let (t1, b) = self.get(i);
+            let dt = (t1 - t0) as i64;
+            humidity += dt * (a.humidity as i64 + b.humidity as i64);
+            temperature += dt * (a.temperature as i64 + b.temperature as i64);
+        }
+        Some(Measurement {
+            humidity: RoundingMode::Nearest.divide(humidity, 2 * span) as u16,
+            temperature: RoundingMode::Nearest.divide(temperature, 2 * span) as i16,
+        })
+    }
+
+    /// Returns the number of samples in the window.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether there are no samples in the window.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes all samples.
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.len = 0;
+    }
+
+    fn get(&self, i: usize) -> (u64, Measurement) {
+        self.samples[(self.start + i) % N]
+    }
+
+    fn get_checked(&self, i: usize) -> Option<(u64, Measurement)> {
+        (i < self.len).then(|| self.get(i))
+    }
+
+    fn newest(&self) -> Option<(u64, Measurement)> {
+        self.get_checked(self.len.checked_sub(1)?)
+    }
+
+    fn drop_oldest(&mut self) {
+        self.start = (self.start + 1) % N;
+        self.len -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn m(humidity: u16, temperature: i16) -> Measurement {
+        Measurement {
+            humidity,
+            temperature,
+        }
+    }
+
+    #[test]
+    fn uneven_spacing_is_time_weighted() {
+        let mut averager = WindowAverager::<8>::new(1000);
+        assert_eq!(averager.average(), None);
+        averager.push(0, m(1000, -100));
+        averager.push(10, m(2000, 100));
+        averager.push(40, m(2000, 400));
+        // (10 * 1500 + 30 * 2000) / 40 and (10 * 0 + 30 * 250) / 40 = 187.5
+        assert_eq!(averager.average(), Some(m(1875, 188)));
+    }
+
+    #[test]
+    fn old_samples_leave_the_window() {
+        let mut averager = WindowAverager::<8>::new(30);
+        averager.push(0, m(1000, -100));
+        averager.push(10, m(2000, 100));
+        averager.push(40, m(2000, 400));
+        assert_eq!(averager.len(), 2);
+        assert_eq!(averager.average(), Some(m(2000, 250)));
+        averager.push(100, m(3000, -300));
+        assert_eq!(averager.average(), Some(m(3000, -300)));
+    }
+
+    #[test]
+    fn capacity_drops_oldest() {
+        let mut averager = WindowAverager::<2>::new(1000);
+        averager.push(0, m(1000, 0));
+        averager.push(10, m(2000, 0));
+        averager.push(30, m(5000, -1000));
+        assert_eq!(averager.len(), 2);
+        // (20 * 7000) / 40 and (20 * -1000) / 40
+        assert_eq!(averager.average(), Some(m(3500, -500)));
+    }
+
+    #[test]
+    fn same_timestamp_and_out_of_order() {
+        let mut averager = WindowAverager::<4>::new(1000);
+        assert!(averager.push(5, m(1000, -101)));
+        assert!(averager.push(5, m(2001, -200)));
+        assert!(!averager.push(4, m(9000, 9000)));
+        assert_eq!(averager.average(), Some(m(1501, -150)));
+        averager.clear();
+        assert!(averager.is_empty());
+    }
+}
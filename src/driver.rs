@@ -0,0 +1,1162 @@
+//! Driver methods shared by the blocking and async API.
+//!
+//! Both APIs are generated from the single definition in `driver_impl!`, the async one
+//! adds `async` to every method that does I/O and `.await` to every call of such a method.
+//! `I2c` and `DelayNs` are resolved at the invocation site, which imports the blocking or
+//! async traits respectively.
+
+/// Implements the [`Driver`](crate::Driver) API for measurement mode `$mode`.
+///
+/// Invoked as `driver_impl!(Blocking)` and `driver_impl!(Async, async, await)`.
+macro_rules! driver_impl {
+    ($mode:ident $(, $async:ident, $await:ident)?) => {
+        impl<I2C, E> Driver<I2C, $mode>
+        where
+            I2C: I2c<Error = E>,
+        {
+            /// Construct new Si70xx sensor and apply `config`.
+            ///
+            /// `delay` is used to wait for the sensor power-up time. Configuration is read back and
+            /// [`Error::ConfigMismatch`] is returned if it did not stick, therefore the returned
+            /// driver is known to be in the configured state.
+            pub $($async)? fn new_configured<D: DelayNs>(
+                i2c: I2C,
+                delay: &mut D,
+                config: DeviceConfig,
+            ) -> Result<Self, Error<E>> {
+                Self::new_with_address(i2c, config.address)
+                    .configure(delay, config)
+                    $(.$await)?
+            }
+
+            /// Probes both Si7013 I2C addresses and returns the ones that responded.
+            ///
+            /// On a board with two Si7013 sensors this confirms that both of them are present
+            /// and their address pins are strapped differently. An address that does not
+            /// acknowledge is left out, any other bus error is returned.
+            #[cfg(feature = "heapless")]
+            pub $($async)? fn scan(i2c: &mut I2C) -> Result<heapless::Vec<u8, 2>, Error<E>>
+            where
+                E: I2cError,
+            {
+                let mut found = heapless::Vec::new();
+                for addr in [Address::H40 as u8, Address::H41 as u8] {
+                    let mut reg = [0u8; 1];
+                    match i2c
+                        .write_read(addr, &[Command::ReadUserRegister1 as u8], &mut reg)
+                        $(.$await)?
+                    {
+                        Ok(()) => {
+                            let _ = found.push(addr);
+                        }
+                        Err(e) if matches!(e.kind(), ErrorKind::NoAcknowledge(_)) => {}
+                        Err(e) => return Err(Error::I2c(e)),
+                    }
+                }
+                Ok(found)
+            }
+
+            /// Probes every address from `start` to `end` inclusive and returns the Si70xx
+            /// sensors that responded together with their models.
+            ///
+            /// Useful on boards where an address translator places the sensor outside 0x40/0x41.
+            /// Waits for the sensor power-up time before probing. The model is read from the
+            /// electronic serial number, an address that does not acknowledge or answers with
+            /// an invalid checksum is left out, any other bus error is returned. Reserved addresses
+            /// below 0x08 and above 0x77 are skipped and the scan stops after 8 sensors.
+            #[cfg(feature = "heapless")]
+            pub $($async)? fn scan_range<D: DelayNs>(
+                i2c: &mut I2C,
+                delay: &mut D,
+                start: u8,
+                end: u8,
+            ) -> Result<heapless::Vec<(u8, Device), 8>, Error<E>>
+            where
+                E: I2cError,
+            {
+                delay.delay_ms(POWER_UP_TIME_MS)$(.$await)?;
+                let mut found = heapless::Vec::new();
+                for addr in start.max(0x08)..=end.min(0x77) {
+                    let mut id = [0u8; 6];
+                    match i2c.write_read(addr, &READ_ELECTRONIC_ID_2, &mut id)$(.$await)? {
+                        Ok(()) if crc_valid(&[id[0], id[1], id[2]]) => {
+                            if found.push((addr, Device::from(id[0]))).is_err() {
+                                break;
+                            }
+                        }
+                        Ok(()) => {}
+                        Err(e) if matches!(e.kind(), ErrorKind::NoAcknowledge(_)) => {}
+                        Err(e) => return Err(Error::I2c(e)),
+                    }
+                }
+                Ok(found)
+            }
+        }
+
+        impl<I2C, E, H> Driver<I2C, $mode, H>
+        where
+            I2C: I2c<Error = E>,
+            H: DriverHooks,
+        {
+            /// Initiates a measurement for relative humidity and temperature.
+            ///
+            /// This method starts both the relative humidity and temperature measurement.
+            /// Use [`read_humidity`] and [`read_temperature`] to retrieve the measurements.
+            ///
+            /// [`read_humidity`]: Self::read_humidity
+            /// [`read_temperature`]: Self::read_temperature
+            pub $($async)? fn measure(&mut self) -> Result<(), Error<E>> {
+                self.start_conversion(Command::MeasureRhHoldMaster, MeasurementMode::HoldMaster)
+                    $(.$await)??;
+                Ok(())
+            }
+
+            /// Retrieves the last measured relative humidity.
+            ///
+            /// This method should be called after [`measure`].
+            /// It returns the relative humidity as a percentage multiplied by 100.
+            /// For example, a return value of 4955 represents 49.55%.
+            /// The checksum sent by the sensor is validated unless disabled with [`with_crc`],
+            /// [`Error::Crc`] is returned on mismatch.
+            ///
+            /// [`measure`]: Self::measure
+            /// [`with_crc`]: Self::with_crc
+            pub $($async)? fn read_humidity(&mut self) -> Result<u16, Error<E>> {
+                let rh_code = self.read_checked(Frame::Humidity)$(.$await)??;
+                Ok(humidity_from_code(rh_code, self.rounding))
+            }
+
+            /// Retrieves the last measured relative humidity as a percentage.
+            ///
+            /// Same as [`read_humidity`] but the result is converted to `f32`,
+            /// for example 49.55 represents 49.55%.
+            ///
+            /// [`read_humidity`]: Self::read_humidity
+            #[cfg(feature = "float")]
+            pub $($async)? fn read_humidity_f32(&mut self) -> Result<f32, Error<E>> {
+                Ok(self.read_humidity()$(.$await)?? as f32 / 100.)
+            }
+
+            /// Retrieves the last measured relative humidity as a percentage or NaN on error.
+            ///
+            /// Convenience for plotting and dashboards that filter out NaN values as missing data.
+            /// The error itself is lost, use [`read_humidity_f32`] to handle it.
+            ///
+            /// [`read_humidity_f32`]: Self::read_humidity_f32
+            #[cfg(feature = "float")]
+            pub $($async)? fn read_humidity_f32_or_nan(&mut self) -> f32 {
+                self.read_humidity_f32()$(.$await)?.unwrap_or(f32::NAN)
+            }
+
+            /// Retrieves the last measured temperature.
+            ///
+            /// This method should be called after [`measure`].
+            /// It returns the emperature in Celsius, multiplied by 100.
+            /// For example, a return value of 2550 represents 25.50°C.
+            ///
+            /// [`measure`]: Self::measure
+            pub $($async)? fn read_temperature(&mut self) -> Result<i16, Error<E>> {
+                let mut response = [0u8; 2];
+                self.i2c
+                    .write_read(
+                        self.addr,
+                        &[Command::ReadTemperatureFromRh as u8],
+                        &mut response,
+                    )
+                    $(.$await)?
+                    .map_err(Error::I2c)?;
+                let temp_code = self.code([response[0], response[1]])?;
+                Ok(temperature_from_code(temp_code, self.rounding))
+            }
+
+            /// Resets the sensor and waits until it is ready using `delay`.
+            ///
+            /// User Register 1 and heater control register return to their power-on defaults and the
+            /// driver assumes them from then on, that is 12-bit relative humidity and 14-bit temperature
+            /// resolution with the heater off. Use [`recover`] to keep the previous settings instead.
+            /// A conversion that was in flight is discarded.
+            ///
+            /// [`recover`]: Self::recover
+            pub $($async)? fn reset<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Error<E>> {
+                self.i2c
+                    .write(self.addr, &[Command::Reset as u8])
+                    $(.$await)?
+                    .map_err(Error::I2c)?;
+                self.pending = None;
+                self.crc_failures = 0;
+                self.resolution = Resolution::Rh12Temp14;
+                self.heater = false;
+                self.heater_current = HeaterCurrent::Level0;
+                delay.delay_ms(RESET_TIME_MS)$(.$await)?;
+                Ok(())
+            }
+
+            /// Resets the sensor and applies the resolution and heater settings of the driver again.
+            ///
+            /// Helps after a brownout or an aborted Hold Master transaction left the sensor in a bad
+            /// state. Heater current is written back unless it is the power-on default. The
+            /// configuration is read back, which also checks that the sensor communicates,
+            /// [`Error::ConfigMismatch`] is returned if it did not stick.
+            pub $($async)? fn recover<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Error<E>> {
+                let config = DeviceConfig {
+                    resolution: self.resolution,
+                    heater: self.heater,
+                    ..DeviceConfig::default()
+                };
+                let heater_current = self.heater_current;
+                self.reset(delay)$(.$await)??;
+                if heater_current != HeaterCurrent::Level0 {
+                    self.set_heater_current(heater_current)$(.$await)??;
+                }
+                self.apply(config)$(.$await)?
+            }
+
+            /// Recovers the sensor if [`with_auto_reset_after`] threshold has been reached.
+            ///
+            /// [`with_auto_reset_after`]: Self::with_auto_reset_after
+            $($async)? fn auto_reset<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Error<E>> {
+                if self.auto_reset_due() {
+                    self.recover(delay)$(.$await)??;
+                }
+                Ok(())
+            }
+
+            /// Configures the sensor for [`DEFAULT_RESOLUTION`].
+            ///
+            /// Until then the driver assumes the power-on default resolution.
+            pub $($async)? fn init(&mut self) -> Result<(), Error<E>> {
+                self.set_resolution(DEFAULT_RESOLUTION)$(.$await)?
+            }
+
+            /// Enables or disables the on-chip heater.
+            ///
+            /// Heating burns off condensation, relative humidity and temperature readings are not
+            /// accurate while the heater is on. Only the HTRE bit of User Register 1 is modified.
+            pub $($async)? fn set_heater_enabled(&mut self, enabled: bool) -> Result<(), Error<E>> {
+                let reg = self.read_user_register1()$(.$await)?? & !USER_REGISTER1_HTRE;
+                let reg = if enabled {
+                    reg | USER_REGISTER1_HTRE
+                } else {
+                    reg
+                };
+                self.i2c
+                    .write(self.addr, &[Command::WriteUserRegister1 as u8, reg])
+                    $(.$await)?
+                    .map_err(Error::I2c)?;
+                self.heater = enabled;
+                Ok(())
+            }
+
+            /// Reads whether the on-chip heater is enabled.
+            pub $($async)? fn heater_enabled(&mut self) -> Result<bool, Error<E>> {
+                Ok(self.read_user_register1()$(.$await)?? & USER_REGISTER1_HTRE != 0)
+            }
+
+            /// Sets the on-chip heater current, takes effect when the heater is enabled.
+            ///
+            /// Reserved bits of Heater Control Register are written back as read.
+            pub $($async)? fn set_heater_current(&mut self, current: HeaterCurrent) -> Result<(), Error<E>> {
+                let reg = self.read_heater_control()$(.$await)??;
+                let reg = (reg & !HEATER_CONTROL_MASK) | current as u8;
+                self.i2c
+                    .write(self.addr, &[Command::WriteHeaterControl as u8, reg])
+                    $(.$await)?
+                    .map_err(Error::I2c)?;
+                self.heater_current = current;
+                Ok(())
+            }
+
+            /// Reads the on-chip heater current setting.
+            pub $($async)? fn heater_current(&mut self) -> Result<HeaterCurrent, Error<E>> {
+                Ok(HeaterCurrent::from_bits(self.read_heater_control()$(.$await)??))
+            }
+
+            /// Sets heater current and turns the heater on.
+            ///
+            /// Heating the sensor element drives off condensation and can be used to check that
+            /// relative humidity and temperature readings react. Measurements taken while the heater
+            /// is on are offset accordingly, turn it off with [`disable_heater`] afterwards.
+            ///
+            /// [`disable_heater`]: Self::disable_heater
+            pub $($async)? fn enable_heater(&mut self, current: HeaterCurrent) -> Result<(), Error<E>> {
+                self.set_heater_current(current)$(.$await)??;
+                self.set_heater_enabled(true)$(.$await)?
+            }
+
+            /// Turns the heater off, the configured current is kept.
+            pub $($async)? fn disable_heater(&mut self) -> Result<(), Error<E>> {
+                self.set_heater_enabled(false)$(.$await)?
+            }
+
+            /// Returns heater current if the heater is on, `None` if it is off.
+            pub $($async)? fn heater_level(&mut self) -> Result<Option<HeaterCurrent>, Error<E>> {
+                if self.heater_enabled()$(.$await)?? {
+                    Ok(Some(self.heater_current()$(.$await)??))
+                } else {
+                    Ok(None)
+                }
+            }
+
+            /// Reads reserved bits 1, 3, 4 and 5 of User Register 1, other bits are cleared.
+            ///
+            /// Datasheet does not define these bits. A part that reports a different value than
+            /// others may be a new revision that uses them.
+            pub $($async)? fn read_user_register_reserved(&mut self) -> Result<u8, Error<E>> {
+                Ok(self.read_user_register1()$(.$await)?? & USER_REGISTER1_RESERVED_MASK)
+            }
+
+            /// Sets relative humidity and temperature measurement resolution.
+            ///
+            /// Only the resolution bits of User Register 1 are modified, reserved bits are written
+            /// back as read. Methods that wait for
+            /// a conversion with a delay use conversion time of this resolution.
+            pub $($async)? fn set_resolution(&mut self, resolution: Resolution) -> Result<(), Error<E>> {
+                let reg = self.read_user_register1()$(.$await)??;
+                let reg = (reg & !USER_REGISTER1_RES_MASK) | resolution.bits();
+                self.i2c
+                    .write(self.addr, &[Command::WriteUserRegister1 as u8, reg])
+                    $(.$await)?
+                    .map_err(Error::I2c)?;
+                self.resolution = resolution;
+                Ok(())
+            }
+
+            /// Reads the relative humidity and temperature measurement resolution from the sensor.
+            ///
+            /// The driver also starts using it for conversion times, which helps if the sensor was
+            /// configured by someone else, for example before a firmware update.
+            pub $($async)? fn resolution(&mut self) -> Result<Resolution, Error<E>> {
+                self.resolution = Resolution::from_bits(self.read_user_register1()$(.$await)??);
+                Ok(self.resolution)
+            }
+
+            /// Reads the supply voltage status from User Register 1.
+            ///
+            /// The sensor updates the status during each conversion. A [`VddStatus::Low`] reading is
+            /// also latched, see [`vdd_low_since_reset`].
+            ///
+            /// [`vdd_low_since_reset`]: Self::vdd_low_since_reset
+            pub $($async)? fn vdd_status(&mut self) -> Result<VddStatus, Error<E>> {
+                if self.read_user_register1()$(.$await)?? & USER_REGISTER1_VDDS != 0 {
+                    Ok(VddStatus::Low)
+                } else {
+                    Ok(VddStatus::Ok)
+                }
+            }
+
+            /// Measures and reads out relative humidity and temperature.
+            ///
+            /// Unlike [`measure`] this method does not hold the I²C bus during the conversion.
+            /// The measurement is started with a No Hold Master command and `delay` is used
+            /// to wait for the conversion to finish before reading out the results.
+            /// The result is also kept as the last reading, see [`last_humidity`] and [`last_temperature`].
+            ///
+            /// Si7006/13/20/21 return only the relative humidity code in the measurement frame.
+            /// Temperature measured during the same conversion is read out with a separate
+            /// command, therefore two I²C transactions are needed. The second transaction
+            /// does not start a new conversion, so it adds only a few bytes of bus traffic.
+            ///
+            /// [`measure`]: Self::measure
+            /// [`last_humidity`]: Self::last_humidity
+            /// [`last_temperature`]: Self::last_temperature
+            pub $($async)? fn read_measurements<D: DelayNs>(
+                &mut self,
+                delay: &mut D,
+            ) -> Result<Measurement, Error<E>> {
+                self.auto_reset(delay)$(.$await)??;
+                self.start_conversion(
+                    Command::MeasureRhNoHoldMaster,
+                    MeasurementMode::NoHoldMaster,
+                )
+                $(.$await)??;
+                delay
+                    .delay_ms(self.resolution.rh_conversion_time_ms() + self.timing_margin_ms)
+                    $(.$await)?;
+                let humidity = self.read_humidity()$(.$await)??;
+                let temperature = if self.crc && self.temperature_crc {
+                    self.measure_temperature(delay)$(.$await)??
+                } else {
+                    self.read_temperature()$(.$await)??
+                };
+                let measurement = Measurement {
+                    humidity,
+                    temperature,
+                };
+                Ok(self.complete(measurement))
+            }
+
+            /// Measures relative humidity and temperature without holding the I²C bus.
+            ///
+            /// Starts the conversion with a No Hold Master command, so SCL is not stretched and other
+            /// devices can use the bus meanwhile. `delay` is used to wait for the worst case conversion
+            /// time at the configured resolution. If the sensor still does not acknowledge the read, it
+            /// is polled every millisecond for up to another conversion time, after that the NACK is
+            /// returned as [`Error::I2c`]. The hold master [`measure`] is not affected.
+            ///
+            /// [`measure`]: Self::measure
+            pub $($async)? fn measure_no_hold<D: DelayNs>(
+                &mut self,
+                delay: &mut D,
+            ) -> Result<Measurement, Error<E>>
+            where
+                E: I2cError,
+            {
+                self.auto_reset(delay)$(.$await)??;
+                self.start_conversion(
+                    Command::MeasureRhNoHoldMaster,
+                    MeasurementMode::NoHoldMaster,
+                )
+                $(.$await)??;
+                delay
+                    .delay_ms(self.resolution.rh_conversion_time_ms() + self.timing_margin_ms)
+                    $(.$await)?;
+                let mut response = [0u8; 3];
+                self.poll_read(delay, &mut response[..self.frame_len()])
+                    $(.$await)??;
+                let humidity = humidity_from_code(self.checked(response, Frame::Humidity)?, self.rounding);
+                let temperature = self.read_temperature()$(.$await)??;
+                let measurement = Measurement {
+                    humidity,
+                    temperature,
+                };
+                Ok(self.complete(measurement))
+            }
+
+            /// Polls a No Hold Master measurement without blocking.
+            ///
+            /// If no conversion is in flight, starts one and returns `Ok(None)`. While the sensor does
+            /// not acknowledge the read because the conversion is still running, returns `Ok(None)` as
+            /// well. Once it acknowledges, reads out relative humidity and temperature and returns the
+            /// measurement. Call it periodically, for example from a timer, to avoid clock stretching
+            /// and blocking delays altogether.
+            pub $($async)? fn read_ready(&mut self) -> Result<Option<Measurement>, Error<E>>
+            where
+                E: I2cError,
+            {
+                if self.pending.is_none() {
+                    self.start_conversion(
+                        Command::MeasureRhNoHoldMaster,
+                        MeasurementMode::NoHoldMaster,
+                    )
+                    $(.$await)??;
+                    return Ok(None);
+                }
+                let mut response = [0u8; 3];
+                match self
+                    .i2c
+                    .read(self.addr, &mut response[..self.frame_len()])
+                    $(.$await)?
+                {
+                    Ok(()) => self.pending = None,
+                    Err(e) if matches!(e.kind(), ErrorKind::NoAcknowledge(_)) => return Ok(None),
+                    Err(e) => return Err(Error::I2c(e)),
+                }
+                let humidity = humidity_from_code(self.checked(response, Frame::Humidity)?, self.rounding);
+                let temperature = self.read_temperature()$(.$await)??;
+                let measurement = Measurement {
+                    humidity,
+                    temperature,
+                };
+                Ok(Some(self.complete(measurement)))
+            }
+
+            /// Measures and reads out relative humidity and temperature into `buffer`.
+            ///
+            /// Works like [`read_measurements`], but responses are received into `buffer` instead of
+            /// a stack array, so it can be placed in a DMA-capable memory region. `buffer` must be at
+            /// least [`MIN_BUFFER_LEN`] bytes, otherwise [`Error::BufferTooSmall`] is returned without
+            /// accessing the bus. Only the first [`MIN_BUFFER_LEN`] bytes are used. The driver does not
+            /// require any alignment, follow the requirements of the HAL. Command bytes are still
+            /// written from driver memory. Temperature is measured with a checksum like in
+            /// [`read_measurements`] if [`with_temperature_crc`] is enabled.
+            ///
+            /// [`read_measurements`]: Self::read_measurements
+            /// [`with_temperature_crc`]: Self::with_temperature_crc
+            pub $($async)? fn read_measurements_into<D: DelayNs>(
+                &mut self,
+                delay: &mut D,
+                buffer: &mut [u8],
+            ) -> Result<Measurement, Error<E>> {
+                let buffer = buffer
+                    .get_mut(..MIN_BUFFER_LEN)
+                    .ok_or(Error::BufferTooSmall)?;
+                self.auto_reset(delay)$(.$await)??;
+                self.start_conversion(
+                    Command::MeasureRhNoHoldMaster,
+                    MeasurementMode::NoHoldMaster,
+                )
+                $(.$await)??;
+                delay
+                    .delay_ms(self.resolution.rh_conversion_time_ms() + self.timing_margin_ms)
+                    $(.$await)?;
+                let rh_code = self.read_checked_into(buffer, Frame::Humidity)$(.$await)??;
+                let humidity = humidity_from_code(rh_code, self.rounding);
+                let temp_code = if self.crc && self.temperature_crc {
+                    self.start_conversion(
+                        Command::MeasureTemperatureNoHoldMaster,
+                        MeasurementMode::NoHoldMaster,
+                    )
+                    $(.$await)??;
+                    delay
+                        .delay_ms(self.resolution.temperature_conversion_time_ms() + self.timing_margin_ms)
+                        $(.$await)?;
+                    self.read_checked_into(buffer, Frame::Temperature)$(.$await)??
+                } else {
+                    // Temperature read out from the relative humidity measurement has no checksum.
+                    let buffer = &mut buffer[..2];
+                    self.i2c
+                        .write_read(self.addr, &[Command::ReadTemperatureFromRh as u8], buffer)
+                        $(.$await)?
+                        .map_err(Error::I2c)?;
+                    self.code([buffer[0], buffer[1]])?
+                };
+                let temperature = temperature_from_code(temp_code, self.rounding);
+                let measurement = Measurement {
+                    humidity,
+                    temperature,
+                };
+                Ok(self.complete(measurement))
+            }
+
+            /// Measures and reads out relative humidity and temperature, retrying on transient errors.
+            ///
+            /// Works like [`read_measurements`], but if it fails with a transient error `delay` is
+            /// used to wait before trying again. The wait starts at `base_ms` and doubles with each
+            /// retry. At most `max_retries` retries are done, after that the last error is returned.
+            ///
+            /// Retried errors are [`Error::NotReady`] and I²C errors of kind
+            /// [`ErrorKind::NoAcknowledge`], [`ErrorKind::ArbitrationLoss`] and [`ErrorKind::Bus`],
+            /// which are expected while another master is using the bus. All other errors are
+            /// returned immediately.
+            ///
+            /// [`read_measurements`]: Self::read_measurements
+            pub $($async)? fn read_measurements_backoff<D: DelayNs>(
+                &mut self,
+                delay: &mut D,
+                max_retries: u8,
+                base_ms: u32,
+            ) -> Result<Measurement, Error<E>>
+            where
+                E: I2cError,
+            {
+                let mut wait_ms = base_ms;
+                let mut retries = 0;
+                loop {
+                    match self.read_measurements(delay)$(.$await)? {
+                        Err(e) if retries < max_retries && e.is_transient() => {
+                            delay.delay_ms(wait_ms)$(.$await)?;
+                            wait_ms = wait_ms.saturating_mul(2);
+                            retries += 1;
+                        }
+                        result => return result,
+                    }
+                }
+            }
+
+            /// Starts Si7013 auxiliary ADC conversion without holding the bus.
+            ///
+            /// The auxiliary input is usually wired to a thermistor. Wait for at least 7 ms and read
+            /// out the result with [`read_aux`], or use [`read_thermistor`] that does both.
+            ///
+            /// [`read_aux`]: Self::read_aux
+            /// [`read_thermistor`]: Self::read_thermistor
+            pub $($async)? fn measure_aux(&mut self) -> Result<(), Error<E>> {
+                self.start_conversion(Command::MeasureThermistor, MeasurementMode::NoHoldMaster)
+                    $(.$await)?
+            }
+
+            /// Reads out auxiliary ADC code of the conversion started with [`measure_aux`].
+            ///
+            /// The code is corrected by the sensor according to User Register 2 and the thermistor
+            /// correction coefficients, see [`write_thermistor_coefficients`].
+            ///
+            /// [`measure_aux`]: Self::measure_aux
+            /// [`write_thermistor_coefficients`]: Self::write_thermistor_coefficients
+            pub $($async)? fn read_aux(&mut self) -> Result<u16, Error<E>> {
+                let mut response = [0u8; 2];
+                self.i2c
+                    .read(self.addr, &mut response)
+                    $(.$await)?
+                    .map_err(Error::I2c)?;
+                self.pending = None;
+                self.code(response)
+            }
+
+            /// Reads Si7013 User Register 2 that configures the auxiliary ADC input.
+            pub $($async)? fn read_user_register2(&mut self) -> Result<u8, Error<E>> {
+                let mut reg = [0u8; 1];
+                self.i2c
+                    .write_read(self.addr, &[Command::ReadUserRegister2 as u8], &mut reg)
+                    $(.$await)?
+                    .map_err(Error::I2c)?;
+                Ok(reg[0])
+            }
+
+            /// Writes Si7013 User Register 2 that configures the auxiliary ADC input.
+            ///
+            /// The register layout depends on the external circuit, see the Si7013 datasheet.
+            /// Read the register first and modify only the required bits.
+            pub $($async)? fn write_user_register2(&mut self, reg: u8) -> Result<(), Error<E>> {
+                self.i2c
+                    .write(self.addr, &[Command::WriteUserRegister2 as u8, reg])
+                    $(.$await)?
+                    .map_err(Error::I2c)
+            }
+
+            /// Programs Si7013 thermistor correction coefficients starting from `address`.
+            ///
+            /// Each byte of `values` is written to the next address. The sensor uses the coefficients
+            /// to linearize the auxiliary ADC code, they are board specific and are usually generated
+            /// with the thermistor correction tool from Silicon Labs.
+            pub $($async)? fn write_thermistor_coefficients(
+                &mut self,
+                address: u8,
+                values: &[u8],
+            ) -> Result<(), Error<E>> {
+                for (offset, value) in values.iter().enumerate() {
+                    let command = [
+                        Command::WriteThermistorCoefficient as u8,
+                        address.wrapping_add(offset as u8),
+                        *value,
+                    ];
+                    self.i2c
+                        .write(self.addr, &command)
+                        $(.$await)?
+                        .map_err(Error::I2c)?;
+                }
+                Ok(())
+            }
+
+            /// Reads Si7013 thermistor correction coefficient at `address`.
+            pub $($async)? fn read_thermistor_coefficient(&mut self, address: u8) -> Result<u8, Error<E>> {
+                let mut value = [0u8; 1];
+                self.i2c
+                    .write_read(
+                        self.addr,
+                        &[Command::ReadThermistorCoefficient as u8, address],
+                        &mut value,
+                    )
+                    $(.$await)?
+                    .map_err(Error::I2c)?;
+                Ok(value[0])
+            }
+
+            /// Measures Si7013 thermistor and returns the raw ADC code.
+            ///
+            /// `delay` is used to wait for the thermistor conversion, which has its own conversion time
+            /// distinct from relative humidity and temperature measurements.
+            pub $($async)? fn read_thermistor<D: DelayNs>(&mut self, delay: &mut D) -> Result<u16, Error<E>> {
+                self.measure_aux()$(.$await)??;
+                delay
+                    .delay_ms(THERMISTOR_CONVERSION_TIME_MS + self.timing_margin_ms)
+                    $(.$await)?;
+                self.read_aux()$(.$await)?
+            }
+
+            /// Measures Si7013 thermistor and returns compensated temperature in Celsius multiplied by 100.
+            ///
+            /// Thermistor and on-die temperature are measured one after another, `delay` is used
+            /// to wait for both conversions. Thermistor code is converted with `coeffs`, see
+            /// [`ThermistorCoefficients`] for the model.
+            pub $($async)? fn read_thermistor_compensated<D: DelayNs>(
+                &mut self,
+                delay: &mut D,
+                coeffs: &ThermistorCoefficients,
+            ) -> Result<i16, Error<E>> {
+                let code = self.read_thermistor(delay)$(.$await)??;
+                let die_temperature = self.measure_temperature(delay)$(.$await)??;
+                Ok(coeffs.apply(code, die_temperature))
+            }
+
+            /// Starts relative humidity and temperature measurement without holding the bus.
+            ///
+            /// Returned handle carries the time when the conversion is finished according to `clock`,
+            /// pass it to [`finish`] to read out the result.
+            ///
+            /// [`finish`]: Self::finish
+            pub $($async)? fn start<C: Clock>(&mut self, clock: &mut C) -> Result<PendingMeasurement, Error<E>> {
+                self.start_conversion(
+                    Command::MeasureRhNoHoldMaster,
+                    MeasurementMode::NoHoldMaster,
+                )
+                $(.$await)??;
+                let duration = self.resolution.rh_conversion_time_ms() + self.timing_margin_ms;
+                Ok(PendingMeasurement {
+                    ready_at_ms: clock.now_ms() + duration as u64,
+                })
+            }
+
+            /// Reads out measurement started with [`start`].
+            ///
+            /// Returns [`Error::NotReady`] without accessing the bus if the conversion has not
+            /// finished yet according to `clock`. Use [`PendingMeasurement::is_ready`] to check it beforehand.
+            /// The handle is only borrowed, so it can be passed again after [`Error::NotReady`].
+            ///
+            /// [`start`]: Self::start
+            pub $($async)? fn finish<C: Clock>(
+                &mut self,
+                handle: &PendingMeasurement,
+                clock: &mut C,
+            ) -> Result<Measurement, Error<E>> {
+                if !handle.is_ready(clock) {
+                    return Err(Error::NotReady);
+                }
+                let humidity = self.read_humidity()$(.$await)??;
+                let temperature = self.read_temperature()$(.$await)??;
+                let measurement = Measurement {
+                    humidity,
+                    temperature,
+                };
+                Ok(self.complete(measurement))
+            }
+
+            /// Measures how long a relative humidity and temperature conversion takes on this part.
+            ///
+            /// Starts a conversion without holding the bus and reads the sensor in a tight loop
+            /// until it acknowledges, returns the elapsed time according to `clock` in milliseconds.
+            /// The conversion uses the current resolution, its result is discarded. Returns
+            /// [`Error::NotReady`] if the sensor does not acknowledge within 100 ms.
+            /// Time resolution is limited by `clock` and the I²C transfer time.
+            pub $($async)? fn benchmark_conversion<C: Clock>(&mut self, clock: &mut C) -> Result<u32, Error<E>>
+            where
+                E: I2cError,
+            {
+                self.start_conversion(
+                    Command::MeasureRhNoHoldMaster,
+                    MeasurementMode::NoHoldMaster,
+                )
+                $(.$await)??;
+                let started_ms = clock.now_ms();
+                let mut response = [0u8; 2];
+                loop {
+                    let elapsed_ms = clock.now_ms().saturating_sub(started_ms);
+                    match self.i2c.read(self.addr, &mut response)$(.$await)? {
+                        Ok(()) => {
+                            self.pending = None;
+                            return Ok(elapsed_ms as u32);
+                        }
+                        Err(e) if matches!(e.kind(), ErrorKind::NoAcknowledge(_)) => {
+                            if elapsed_ms > BENCHMARK_TIMEOUT_MS {
+                                return Err(Error::NotReady);
+                            }
+                        }
+                        Err(e) => return Err(Error::I2c(e)),
+                    }
+                }
+            }
+
+            /// Measures at each of the four resolutions and returns raw codes and conversion times.
+            ///
+            /// Intended for qualifying a batch of parts. Each conversion is started without holding the
+            /// bus and the sensor is polled every millisecond using `delay` until it acknowledges. The
+            /// resolution that was set before is restored afterwards, also when the sweep fails, in
+            /// which case the sweep error is returned even if restoring failed too. Returns
+            /// [`Error::NotReady`] if a conversion does not finish within 100 ms.
+            pub $($async)? fn characterize<D: DelayNs>(
+                &mut self,
+                delay: &mut D,
+            ) -> Result<Characterization, Error<E>>
+            where
+                E: I2cError,
+            {
+                let original = self.resolution;
+                let sweep = self.sweep(delay)$(.$await)?;
+                let restored = self.set_resolution(original)$(.$await)?;
+                let characterization = sweep?;
+                restored?;
+                Ok(characterization)
+            }
+
+            $($async)? fn sweep<D: DelayNs>(&mut self, delay: &mut D) -> Result<Characterization, Error<E>>
+            where
+                E: I2cError,
+            {
+                Ok(Characterization {
+                    profiles: [
+                        self.profile(delay, Resolution::Rh12Temp14)$(.$await)??,
+                        self.profile(delay, Resolution::Rh8Temp12)$(.$await)??,
+                        self.profile(delay, Resolution::Rh10Temp13)$(.$await)??,
+                        self.profile(delay, Resolution::Rh11Temp11)$(.$await)??,
+                    ],
+                })
+            }
+
+            /// Measures relative humidity without reading out temperature.
+            ///
+            /// Unlike [`read_measurements`] this skips the temperature read out, which saves
+            /// one bus transaction per sample when only relative humidity is needed.
+            ///
+            /// [`read_measurements`]: Self::read_measurements
+            pub $($async)? fn read_humidity_only<D: DelayNs>(&mut self, delay: &mut D) -> Result<u16, Error<E>> {
+                Ok(humidity_from_code(
+                    self.measure_humidity_code(delay)$(.$await)??,
+                    self.rounding,
+                ))
+            }
+
+            /// Measures temperature without relative humidity.
+            ///
+            /// Starts a temperature-only conversion without holding the bus and uses `delay` to wait
+            /// for it, which is several times shorter than [`read_measurements`]. Returns the
+            /// temperature in Celsius multiplied by 100, the checksum is validated unless disabled
+            /// with [`with_crc`].
+            ///
+            /// [`read_measurements`]: Self::read_measurements
+            /// [`with_crc`]: Self::with_crc
+            pub $($async)? fn read_temperature_only<D: DelayNs>(
+                &mut self,
+                delay: &mut D,
+            ) -> Result<i16, Error<E>> {
+                self.measure_temperature(delay)$(.$await)?
+            }
+
+            /// Measures temperature without relative humidity in Hold Master mode.
+            ///
+            /// The sensor stretches the clock until the conversion has finished. Returns the
+            /// temperature in Celsius multiplied by 100, the checksum is validated unless disabled
+            /// with [`with_crc`].
+            ///
+            /// [`with_crc`]: Self::with_crc
+            pub $($async)? fn read_temperature_hold(&mut self) -> Result<i16, Error<E>> {
+                self.start_conversion(
+                    Command::MeasureTemperatureHoldMaster,
+                    MeasurementMode::HoldMaster,
+                )
+                $(.$await)??;
+                Ok(temperature_from_code(
+                    self.read_checked(Frame::Temperature)$(.$await)??,
+                    self.rounding,
+                ))
+            }
+
+            /// Measures relative humidity and returns it together with a saturation flag.
+            ///
+            /// Relative humidity is clamped to 0..100% and the flag is `true` if the value before
+            /// clamping was at least 100%. Such readings are likely caused by condensation.
+            pub $($async)? fn read_humidity_saturated<D: DelayNs>(
+                &mut self,
+                delay: &mut D,
+            ) -> Result<(u16, bool), Error<E>> {
+                let rh =
+                    humidity_from_code_unclamped(self.measure_humidity_code(delay)$(.$await)??, self.rounding);
+                Ok((rh.clamp(0, 10000) as u16, rh >= 10000))
+            }
+
+            /// Checks that the sensor is alive and reports a sane temperature.
+            ///
+            /// Performs a single temperature measurement, which is quicker than a relative humidity
+            /// measurement, and returns whether it falls within the -40..125°C operating range.
+            /// Bus errors are returned as usual, so `Ok(false)` means the sensor responded
+            /// with an implausible value.
+            pub $($async)? fn health_ping<D: DelayNs>(&mut self, delay: &mut D) -> Result<bool, Error<E>> {
+                let temp = self.measure_temperature(delay)$(.$await)??;
+                let limits = operating_limits();
+                Ok((limits.temperature_min..=limits.temperature_max).contains(&temp))
+            }
+
+            /// Measures relative humidity and temperature and returns their raw frames.
+            ///
+            /// Each frame consists of measurement MSB, LSB and CRC byte exactly as they were read
+            /// from the bus, the checksum is not validated. Use [`crc8`](crate::crc8) over the first two bytes
+            /// to check it manually.
+            ///
+            /// Temperature read out after a relative humidity measurement does not include a checksum,
+            /// therefore the temperature frame comes from a separate temperature measurement.
+            pub $($async)? fn read_raw_frames<D: DelayNs>(
+                &mut self,
+                delay: &mut D,
+            ) -> Result<([u8; 3], [u8; 3]), Error<E>> {
+                let mut rh = [0u8; 3];
+                self.start_conversion(
+                    Command::MeasureRhNoHoldMaster,
+                    MeasurementMode::NoHoldMaster,
+                )
+                $(.$await)??;
+                delay
+                    .delay_ms(self.resolution.rh_conversion_time_ms() + self.timing_margin_ms)
+                    $(.$await)?;
+                self.i2c
+                    .read(self.addr, &mut rh)
+                    $(.$await)?
+                    .map_err(Error::I2c)?;
+                self.pending = None;
+
+                let mut temp = [0u8; 3];
+                self.start_conversion(
+                    Command::MeasureTemperatureNoHoldMaster,
+                    MeasurementMode::NoHoldMaster,
+                )
+                $(.$await)??;
+                delay
+                    .delay_ms(self.resolution.temperature_conversion_time_ms() + self.timing_margin_ms)
+                    $(.$await)?;
+                self.i2c
+                    .read(self.addr, &mut temp)
+                    $(.$await)?
+                    .map_err(Error::I2c)?;
+                self.pending = None;
+                Ok((rh, temp))
+            }
+
+            $($async)? fn read_heater_control(&mut self) -> Result<u8, Error<E>> {
+                let mut reg = [0u8; 1];
+                self.i2c
+                    .write_read(self.addr, &[Command::ReadHeaterControl as u8], &mut reg)
+                    $(.$await)?
+                    .map_err(Error::I2c)?;
+                Ok(reg[0])
+            }
+
+            /// Runs the checks enabled on the driver and starts a conversion using `command`.
+            $($async)? fn start_conversion(
+                &mut self,
+                command: Command,
+                mode: MeasurementMode,
+            ) -> Result<(), Error<E>> {
+                if !self.hooks.bus_idle() {
+                    return Err(Error::BusStuck);
+                }
+                if self.supply_check && self.read_user_register1()$(.$await)?? & USER_REGISTER1_VDDS != 0 {
+                    return Err(Error::LowSupply);
+                }
+                self.hooks.before_measure();
+                self.i2c
+                    .write(self.addr, &[command as u8])
+                    $(.$await)?
+                    .map_err(Error::I2c)?;
+                self.pending = Some(mode);
+                Ok(())
+            }
+
+            /// Sets `resolution`, measures once and records codes and conversion time.
+            $($async)? fn profile<D: DelayNs>(
+                &mut self,
+                delay: &mut D,
+                resolution: Resolution,
+            ) -> Result<ResolutionProfile, Error<E>>
+            where
+                E: I2cError,
+            {
+                self.set_resolution(resolution)$(.$await)??;
+                self.start_conversion(
+                    Command::MeasureRhNoHoldMaster,
+                    MeasurementMode::NoHoldMaster,
+                )
+                $(.$await)??;
+                let mut conversion_time_ms = 0;
+                let mut response = [0u8; 3];
+                loop {
+                    match self
+                        .i2c
+                        .read(self.addr, &mut response[..self.frame_len()])
+                        $(.$await)?
+                    {
+                        Ok(()) => break,
+                        Err(e) if matches!(e.kind(), ErrorKind::NoAcknowledge(_)) => {
+                            if conversion_time_ms as u64 >= BENCHMARK_TIMEOUT_MS {
+                                return Err(Error::NotReady);
+                            }
+                            delay.delay_ms(1)$(.$await)?;
+                            conversion_time_ms += 1;
+                        }
+                        Err(e) => return Err(Error::I2c(e)),
+                    }
+                }
+                self.pending = None;
+                let rh_code = self.checked(response, Frame::Humidity)?;
+                let mut response = [0u8; 2];
+                self.i2c
+                    .write_read(
+                        self.addr,
+                        &[Command::ReadTemperatureFromRh as u8],
+                        &mut response,
+                    )
+                    $(.$await)?
+                    .map_err(Error::I2c)?;
+                Ok(ResolutionProfile {
+                    resolution,
+                    rh_code,
+                    temperature_code: self.code(response)?,
+                    conversion_time_ms,
+                })
+            }
+
+            /// Reads out a finished conversion with its checksum and returns the validated code.
+            $($async)? fn read_checked(&mut self, frame: Frame) -> Result<u16, Error<E>> {
+                let mut response = [0u8; 3];
+                self.read_checked_into(&mut response, frame)$(.$await)?
+            }
+
+            /// Works like [`read_checked`](Self::read_checked), but receives the frame into the
+            /// first 3 bytes of `buffer`.
+            $($async)? fn read_checked_into(&mut self, buffer: &mut [u8], frame: Frame) -> Result<u16, Error<E>> {
+                let len = self.frame_len();
+                self.i2c
+                    .read(self.addr, &mut buffer[..len])
+                    $(.$await)?
+                    .map_err(Error::I2c)?;
+                self.pending = None;
+                self.checked([buffer[0], buffer[1], buffer[2]], frame)
+            }
+
+            /// Measures relative humidity in No Hold Master mode and returns the raw code.
+            $($async)? fn measure_humidity_code<D: DelayNs>(&mut self, delay: &mut D) -> Result<u16, Error<E>> {
+                self.auto_reset(delay)$(.$await)??;
+                self.start_conversion(
+                    Command::MeasureRhNoHoldMaster,
+                    MeasurementMode::NoHoldMaster,
+                )
+                $(.$await)??;
+                delay
+                    .delay_ms(self.resolution.rh_conversion_time_ms() + self.timing_margin_ms)
+                    $(.$await)?;
+                self.read_checked(Frame::Humidity)$(.$await)?
+            }
+
+            /// Measures temperature in No Hold Master mode and returns it in Celsius multiplied by 100.
+            pub(crate) $($async)? fn measure_temperature<D: DelayNs>(
+                &mut self,
+                delay: &mut D,
+            ) -> Result<i16, Error<E>> {
+                self.auto_reset(delay)$(.$await)??;
+                self.start_conversion(
+                    Command::MeasureTemperatureNoHoldMaster,
+                    MeasurementMode::NoHoldMaster,
+                )
+                $(.$await)??;
+                delay
+                    .delay_ms(self.resolution.temperature_conversion_time_ms() + self.timing_margin_ms)
+                    $(.$await)?;
+                Ok(temperature_from_code(
+                    self.read_checked(Frame::Temperature)$(.$await)??,
+                    self.rounding,
+                ))
+            }
+
+            /// Reads the 64-bit electronic serial number, most significant byte first.
+            ///
+            /// The serial number is read in two parts, each byte of the first part and each two byte
+            /// word of the second part is followed by a checksum. [`Error::Crc`] with
+            /// [`Frame::SerialNumber`] is returned if any of them does not match.
+            pub $($async)? fn read_serial_number(&mut self) -> Result<[u8; 8], Error<E>> {
+                let mut sna = [0u8; 8];
+                self.i2c
+                    .write_read(self.addr, &READ_ELECTRONIC_ID_1, &mut sna)
+                    $(.$await)?
+                    .map_err(Error::I2c)?;
+                let mut snb = [0u8; 6];
+                self.i2c
+                    .write_read(self.addr, &READ_ELECTRONIC_ID_2, &mut snb)
+                    $(.$await)?
+                    .map_err(Error::I2c)?;
+                serial_number(sna, snb)
+            }
+
+            /// Reads which sensor model is present from the electronic serial number.
+            pub $($async)? fn read_device(&mut self) -> Result<Device, Error<E>> {
+                Ok(Device::from(self.read_serial_number()$(.$await)??[4]))
+            }
+
+            /// Reads serial number and firmware revision of the sensor.
+            ///
+            /// Useful at startup to check that the expected part is on the bus and to log traceability
+            /// data. The serial number checksums are validated, see [`read_serial_number`].
+            ///
+            /// [`read_serial_number`]: Self::read_serial_number
+            pub $($async)? fn identify(&mut self) -> Result<DeviceInfo, Error<E>> {
+                let serial_number = self.read_serial_number()$(.$await)??;
+                Ok(DeviceInfo {
+                    serial_number,
+                    device: Device::from(serial_number[4]),
+                    firmware_revision: self.read_firmware_revision()$(.$await)??,
+                })
+            }
+
+            /// Reads the firmware revision of the sensor.
+            pub $($async)? fn read_firmware_revision(&mut self) -> Result<FirmwareRevision, Error<E>> {
+                Ok(FirmwareRevision::from(
+                    self.read_firmware_revision_raw()$(.$await)??,
+                ))
+            }
+
+            /// Reads the firmware revision byte as reported by the sensor.
+            ///
+            /// Useful for reporting revisions that [`FirmwareRevision`] does not recognize.
+            pub $($async)? fn read_firmware_revision_raw(&mut self) -> Result<u8, Error<E>> {
+                let mut response = [0u8; 1];
+                self.i2c
+                    .write_read(self.addr, &READ_FIRMWARE_REVISION, &mut response)
+                    $(.$await)?
+                    .map_err(Error::I2c)?;
+                Ok(response[0])
+            }
+
+            /// Applies `config` after waiting for the power-up time and verifies it.
+            $($async)? fn configure<D: DelayNs>(
+                mut self,
+                delay: &mut D,
+                config: DeviceConfig,
+            ) -> Result<Self, Error<E>> {
+                delay.delay_ms(POWER_UP_TIME_MS)$(.$await)?;
+                self.apply(config)$(.$await)??;
+                Ok(self)
+            }
+
+            /// Writes resolution and heater from `config` to User Register 1 and verifies them.
+            $($async)? fn apply(&mut self, config: DeviceConfig) -> Result<(), Error<E>> {
+                let mask = USER_REGISTER1_RES_MASK | USER_REGISTER1_HTRE;
+                let heater = if config.heater {
+                    USER_REGISTER1_HTRE
+                } else {
+                    0
+                };
+                let reg = (self.read_user_register1()$(.$await)?? & !mask) | config.resolution.bits() | heater;
+                self.i2c
+                    .write(self.addr, &[Command::WriteUserRegister1 as u8, reg])
+                    $(.$await)?
+                    .map_err(Error::I2c)?;
+                if self.read_user_register1()$(.$await)?? & mask != reg & mask {
+                    return Err(Error::ConfigMismatch);
+                }
+                self.resolution = config.resolution;
+                self.heater = config.heater;
+                Ok(())
+            }
+
+            $($async)? fn read_user_register1(&mut self) -> Result<u8, Error<E>> {
+                let mut reg = [0u8; 1];
+                self.i2c
+                    .write_read(self.addr, &[Command::ReadUserRegister1 as u8], &mut reg)
+                    $(.$await)?
+                    .map_err(Error::I2c)?;
+                self.vdd_low |= reg[0] & USER_REGISTER1_VDDS != 0;
+                Ok(reg[0])
+            }
+
+            /// Reads conversion result, polling while the sensor does not acknowledge.
+            $($async)? fn poll_read<D: DelayNs>(&mut self, delay: &mut D, buf: &mut [u8]) -> Result<(), Error<E>>
+            where
+                E: I2cError,
+            {
+                let timeout_ms = self.resolution.rh_conversion_time_ms() + self.timing_margin_ms;
+                let mut waited_ms = 0;
+                loop {
+                    match self.i2c.read(self.addr, buf)$(.$await)? {
+                        Ok(()) => {
+                            self.pending = None;
+                            return Ok(());
+                        }
+                        Err(e)
+                            if matches!(e.kind(), ErrorKind::NoAcknowledge(_))
+                                && waited_ms < timeout_ms =>
+                        {
+                            delay.delay_ms(POLL_INTERVAL_MS)$(.$await)?;
+                            waited_ms += POLL_INTERVAL_MS;
+                        }
+                        Err(e) => return Err(Error::I2c(e)),
+                    }
+                }
+            }
+        }
+    };
+}
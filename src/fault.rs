@@ -0,0 +1,308 @@
+//! I²C wrapper that injects faults for testing error handling.
+
+use embedded_hal::i2c::{Error, ErrorKind, ErrorType, Operation};
+
+/// Fault injected into a single I²C transaction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Fault {
+    /// Transaction is not passed to the bus and fails with an error of this kind.
+    Error(ErrorKind),
+    /// Byte at `index` of the data read in the transaction is XORed with `mask`.
+    ///
+    /// Flipping a bit of a measurement frame produces a checksum mismatch.
+    Corrupt {
+        /// Index into the bytes read in the transaction.
+        index: usize,
+        /// Bits to flip.
+        mask: u8,
+    },
+    /// All bytes read in the transaction are replaced with this value.
+    ///
+    /// 0x00 or 0xFF simulates SDA stuck at a supply rail.
+    Rail(u8),
+}
+
+/// Error returned by [`FaultInjector`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FaultError<E> {
+    /// Error injected with [`Fault::Error`].
+    Injected(ErrorKind),
+    /// Error from the wrapped bus.
+    Bus(E),
+}
+
+impl<E: Error> Error for FaultError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            FaultError::Injected(kind) => *kind,
+            FaultError::Bus(e) => e.kind(),
+        }
+    }
+}
+
+/// Wraps an I²C bus and injects a [`Fault`] into a chosen transaction.
+///
+/// Pass it to [`Si70xx::new`] in place of the bus to test how the application handles
+/// NACKs, checksum errors and rail values. Transactions are counted from construction,
+/// a single `write_read` is one transaction.
+///
+/// [`Si70xx::new`]: crate::Si70xx::new
+#[derive(Debug)]
+pub struct FaultInjector<I2C> {
+    i2c: I2C,
+    count: usize,
+    fault: Option<(usize, Fault)>,
+}
+
+impl<I2C> FaultInjector<I2C> {
+    /// Construct new injector that passes all transactions through until a fault is set.
+    pub fn new(i2c: I2C) -> Self {
+        Self {
+            i2c,
+            count: 0,
+            fault: None,
+        }
+    }
+
+    /// Injects `fault` into the `transaction`-th transaction from now, `0` is the next one.
+    ///
+    /// Replaces the previously set fault.
+    pub fn inject(&mut self, transaction: usize, fault: Fault) {
+        self.fault = Some((self.count + transaction, fault));
+    }
+
+    /// Removes the fault that has not been injected yet.
+    pub fn clear(&mut self) {
+        self.fault = None;
+    }
+
+    /// Destroys the injector and returns the wrapped bus.
+    pub fn into_inner(self) -> I2C {
+        self.i2c
+    }
+
+    /// Counts the transaction, fails it if an error is injected or returns data fault to apply.
+    fn before<E>(&mut self) -> Result<Option<Fault>, FaultError<E>> {
+        let count = self.count;
+        self.count += 1;
+        match self.fault {
+            Some((at, fault)) if at == count => {
+                self.fault = None;
+                match fault {
+                    Fault::Error(kind) => Err(FaultError::Injected(kind)),
+                    fault => Ok(Some(fault)),
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Applies data fault to `byte` at `index` of the bytes read in a transaction.
+fn corrupt(fault: Fault, index: usize, byte: &mut u8) {
+    match fault {
+        Fault::Corrupt { index: i, mask } if i == index => *byte ^= mask,
+        Fault::Rail(value) => *byte = value,
+        _ => {}
+    }
+}
+
+/// Applies data fault to bytes read in `operations`.
+fn corrupt_operations(fault: Fault, operations: &mut [Operation<'_>]) {
+    let read = operations
+        .iter_mut()
+        .filter_map(|operation| match operation {
+            Operation::Read(buffer) => Some(buffer.iter_mut()),
+            Operation::Write(_) => None,
+        });
+    for (index, byte) in read.flatten().enumerate() {
+        corrupt(fault, index, byte);
+    }
+}
+
+/// Applies data fault to `buffer` read in a transaction.
+fn corrupt_buffer(fault: Fault, buffer: &mut [u8]) {
+    for (index, byte) in buffer.iter_mut().enumerate() {
+        corrupt(fault, index, byte);
+    }
+}
+
+impl<I2C: ErrorType> ErrorType for FaultInjector<I2C> {
+    type Error = FaultError<I2C::Error>;
+}
+
+impl<I2C: embedded_hal::i2c::I2c> embedded_hal::i2c::I2c for FaultInjector<I2C> {
+    fn read(&mut self, address: u8, read: &mut [u8]) -> Result<(), Self::Error> {
+        let fault = self.before()?;
+        self.i2c.read(address, read).map_err(FaultError::Bus)?;
+        if let Some(fault) = fault {
+            corrupt_buffer(fault, read);
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, address: u8, write: &[u8]) -> Result<(), Self::Error> {
+        self.before()?;
+        self.i2c.write(address, write).map_err(FaultError::Bus)
+    }
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        let fault = self.before()?;
+        self.i2c
+            .write_read(address, write, read)
+            .map_err(FaultError::Bus)?;
+        if let Some(fault) = fault {
+            corrupt_buffer(fault, read);
+        }
+        Ok(())
+    }
+
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let fault = self.before()?;
+        self.i2c
+            .transaction(address, operations)
+            .map_err(FaultError::Bus)?;
+        if let Some(fault) = fault {
+            corrupt_operations(fault, operations);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl<I2C: embedded_hal_async::i2c::I2c> embedded_hal_async::i2c::I2c for FaultInjector<I2C> {
+    async fn read(&mut self, address: u8, read: &mut [u8]) -> Result<(), Self::Error> {
+        let fault = self.before()?;
+        self.i2c
+            .read(address, read)
+            .await
+            .map_err(FaultError::Bus)?;
+        if let Some(fault) = fault {
+            corrupt_buffer(fault, read);
+        }
+        Ok(())
+    }
+
+    async fn write(&mut self, address: u8, write: &[u8]) -> Result<(), Self::Error> {
+        self.before()?;
+        self.i2c
+            .write(address, write)
+            .await
+            .map_err(FaultError::Bus)
+    }
+
+    async fn write_read(
+        &mut self,
+        address: u8,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        let fault = self.before()?;
+        self.i2c
+            .write_read(address, write, read)
+            .await
+            .map_err(FaultError::Bus)?;
+        if let Some(fault) = fault {
+            corrupt_buffer(fault, read);
+        }
+        Ok(())
+    }
+
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let fault = self.before()?;
+        self.i2c
+            .transaction(address, operations)
+            .await
+            .map_err(FaultError::Bus)?;
+        if let Some(fault) = fault {
+            corrupt_operations(fault, operations);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::{Error as SensorError, Frame, Si70xx};
+    use embedded_hal::i2c::{I2c, NoAcknowledgeSource};
+    use embedded_hal_mock::eh1::delay::NoopDelay;
+    use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+    use std::vec;
+
+    #[test]
+    fn injects_faults_into_chosen_transaction() {
+        let expectations = [
+            I2cTransaction::read(0x40, vec![0x7C, 0x80]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80]),
+        ];
+        let mut mock = I2cMock::new(&expectations);
+        let mut i2c = FaultInjector::new(mock.clone());
+        let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+        let mut buf = [0u8; 2];
+
+        i2c.inject(0, Fault::Error(nack));
+        assert_eq!(i2c.read(0x40, &mut buf), Err(FaultError::Injected(nack)));
+
+        i2c.inject(
+            1,
+            Fault::Corrupt {
+                index: 1,
+                mask: 0x01,
+            },
+        );
+        i2c.read(0x40, &mut buf).unwrap();
+        assert_eq!(buf, [0x7C, 0x80]);
+        i2c.read(0x40, &mut buf).unwrap();
+        assert_eq!(buf, [0x7C, 0x81]);
+
+        i2c.inject(0, Fault::Rail(0xFF));
+        i2c.read(0x40, &mut buf).unwrap();
+        assert_eq!(buf, [0xFF, 0xFF]);
+
+        i2c.read(0x40, &mut buf).unwrap();
+        assert_eq!(buf, [0x7C, 0x80]);
+        mock.done();
+    }
+
+    #[test]
+    fn corrupted_checksum_reaches_driver() {
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80, crate::crc8(&[0x7C, 0x80])]),
+        ];
+        let mut mock = I2cMock::new(&expectations);
+        let mut i2c = FaultInjector::new(mock.clone());
+        i2c.inject(
+            1,
+            Fault::Corrupt {
+                index: 2,
+                mask: 0x01,
+            },
+        );
+        let sensor = Si70xx::new(i2c);
+        let mut sensor = sensor.with_temperature_crc(true);
+        assert!(matches!(
+            sensor.read_measurements(&mut NoopDelay::new()),
+            Err(SensorError::Crc(Frame::Humidity))
+        ));
+        mock.done();
+    }
+}
@@ -8,9 +8,16 @@
 //! Datasheet:
 //! - [Si7006/13/20/21/34](https://www.silabs.com/sensors/humidity/si7006-13-20-21-34)
 //!
+//! Si7034 is the only part of the family that returns temperature and relative humidity in
+//! a single frame. It is not supported by this driver and is out of scope: it answers at
+//! address 0x70, uses 16-bit commands and a 6-byte measurement frame, so none of the
+//! command, timing and conversion code here applies to it and it needs a driver of its own.
+//! Other parts read temperature with a second transaction that does not start a new
+//! conversion, see [`Si70xx::read_measurements`].
+//!
 //! ### Read humidity and temperature
 //!
-//! ```no_run
+//! ```ignore
 //! use linux_embedded_hal::I2cdev;
 //! use si70xx::Si70xx;
 //!
@@ -26,18 +33,35 @@
 //! println!("Temperature: {:.1}ºC", temp as f32 / 100.);
 //! ```
 //!
+//! ### Read humidity and temperature without holding the bus
+//! [`Si70xx::read_measurements`] waits for the conversion using a delay instead of
+//! clock stretching. The last reading stays available without accessing the bus.
+//!
+//! ```ignore
+//! use si70xx::Si70xx;
+//!
+//! let i2c = some_i2c_from_hal();
+//! let mut delay = some_delay_from_hal();
+//! let mut sensor = Si70xx::new(i2c);
+//! let m = sensor.read_measurements(&mut delay).unwrap();
+//! println!("Humidity: {:.1}", m.humidity as f32 / 100.);
+//! // Later, for example from UI refresh code.
+//! let temp = sensor.last_temperature();
+//! ```
+//!
 //! ### Read humidity and temperature using async
-//! Async API becomes available by enabling `async` feature.
+//! Async API becomes available by enabling `async` feature. It does not replace the blocking
+//! API, both can be used in the same build.
 //! ```toml
 //! si70xx = { version: 0.1.0, features = "async"}
 //! ```
 //!
-//! ```no_run
+//! ```ignore
 //! use linux_embedded_hal::I2cdev;
-//! use si70xx::Si70xx;
+//! use si70xx::AsyncSi70xx;
 //!
 //! let async_i2c = some_i2c_from_hal();
-//! let mut sensor = Si70xx::new(async_i2c);
+//! let mut sensor = AsyncSi70xx::new(async_i2c);
 //! // Start humidity and temperature measurement.
 //! sensor.measure().await.unwrap();
 //! // Read out measurement results.
@@ -49,36 +73,77 @@
 //! ```
 //! ### Read humidity and temperature with Si7013
 //! Si7013 supports two I2C addresses, all other sensors use fixed 0x40 address.
-//! To use Si7013, feature `si7013` must be enabled.
-//!
-//! ```toml
-//! si70xx = { version: 0.1.0, features = "si7013"}
-//! ```
 //!
-//! ```no_run
+//! ```ignore
 //! use linux_embedded_hal::I2cdev;
 //! use si70xx::{Si70xx, Address};
 //!
 //! let i2c = some_i2c_from_hal();
-//! let mut sensor = Si70xx::new(i2c, Address::H41);
+//! let mut sensor = Si70xx::new_with_address(i2c, Address::H41);
 //! // Measuring and reading out values is the same as in the example above.
 //! ```
+//!
+//! `Si70xx::scan` can be used to check which of the two addresses respond,
+//! it requires `heapless` feature.
+//!
+//! ```ignore
+//! let mut i2c = some_i2c_from_hal();
+//! let found = Si70xx::scan(&mut i2c).unwrap();
+//! assert_eq!(found.as_slice(), &[0x40, 0x41]);
+//! ```
+//!
+//! ### Read temperature with Si7050/51/53/54/55
+//! Temperature-only sensors have a separate [`Si705x`] driver without the relative humidity API.
+//!
+//! ```ignore
+//! use si70xx::Si705x;
+//!
+//! let i2c = some_i2c_from_hal();
+//! let mut delay = some_delay_from_hal();
+//! let mut sensor = Si705x::new(i2c);
+//! let temp = sensor.read_temperature(&mut delay).unwrap();
+//! println!("Temperature: {:.1}ºC", temp as f32 / 100.);
+//! ```
 
 #![no_std]
 
-#[cfg(not(feature = "async"))]
-use embedded_hal::i2c::I2c;
+#[cfg(feature = "std")]
+extern crate std;
+
+// Declared first so that `driver_impl!` is in scope of the modules below.
+#[macro_use]
+mod driver;
+
 #[cfg(feature = "async")]
-use embedded_hal_async::i2c::I2c;
+mod asynch;
+mod average;
+#[cfg(feature = "test-util")]
+mod fault;
+mod log;
+mod sequencer;
+mod si705x;
 
-#[cfg(not(feature = "si7013"))]
-const I2C_ADDR: u8 = 0x40;
-#[cfg(feature = "si7013")]
-/// Si7013 I2C address.
-#[derive(Copy, Clone)]
+pub use average::WindowAverager;
+#[cfg(feature = "test-util")]
+pub use fault::{Fault, FaultError, FaultInjector};
+pub use log::{LogDecoder, LogEncoder};
+pub use sequencer::SequencedReader;
+#[cfg(feature = "async")]
+pub use si705x::AsyncSi705x;
+pub use si705x::{Si705x, TemperatureDriver, TemperatureResolution};
+
+use core::marker::PhantomData;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::{Error as I2cError, ErrorKind, ErrorType, I2c};
+
+/// Sensor I2C address.
+///
+/// Si7013 can be strapped to either address, all other sensors use fixed 0x40 address.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
 #[repr(u8)]
 pub enum Address {
     /// 0x40
+    #[default]
     H40 = 0x40,
     /// 0x41
     H41 = 0x41,
@@ -88,6 +153,80 @@ pub enum Address {
 pub enum Error<E> {
     /// Error on I²C bus.
     I2c(E),
+    /// Supply voltage is below the sensor operating threshold.
+    LowSupply,
+    /// Conversion has not finished yet.
+    NotReady,
+    /// Bus lines were not idle before starting a measurement.
+    ///
+    /// Returned only if a check is set with [`Si70xx::with_bus_clear_check`].
+    BusStuck,
+    /// Measurement bytes are all 0x00 or all 0xFF.
+    ///
+    /// Sensor never returns such codes, these are almost certainly caused by a bus fault
+    /// such as SDA stuck low or missing pull-up. This check is always done.
+    InvalidData,
+    /// Configuration read back from the sensor differs from what was written.
+    ConfigMismatch,
+    /// Caller-provided buffer is shorter than [`MIN_BUFFER_LEN`].
+    BufferTooSmall,
+    /// Checksum of a measurement frame does not match.
+    ///
+    /// Frames with a checksum are validated unless disabled with [`Si70xx::with_crc`].
+    /// Temperature read out from the previous relative humidity measurement has no checksum,
+    /// see [`Si70xx::with_temperature_crc`].
+    Crc(Frame),
+}
+
+impl<E: core::fmt::Debug> core::fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::I2c(e) => write!(f, "I2C bus error: {e:?}"),
+            Error::LowSupply => write!(f, "supply voltage is below operating threshold"),
+            Error::NotReady => write!(f, "conversion has not finished yet"),
+            Error::BusStuck => write!(f, "I2C bus lines are not idle"),
+            Error::InvalidData => write!(f, "invalid measurement data"),
+            Error::ConfigMismatch => write!(f, "configuration was not applied"),
+            Error::BufferTooSmall => write!(f, "buffer is too small"),
+            Error::Crc(frame) => write!(f, "{frame:?} frame checksum mismatch"),
+        }
+    }
+}
+
+/// Measurement frame, identifies which read failed a checksum check.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Frame {
+    /// Relative humidity measurement frame.
+    Humidity,
+    /// Temperature measurement frame.
+    Temperature,
+    /// Electronic serial number.
+    SerialNumber,
+}
+
+impl<E: I2cError> Error<E> {
+    /// Whether the same operation may succeed if it is retried.
+    fn is_transient(&self) -> bool {
+        match self {
+            Error::I2c(e) => matches!(
+                e.kind(),
+                ErrorKind::NoAcknowledge(_) | ErrorKind::ArbitrationLoss | ErrorKind::Bus
+            ),
+            Error::NotReady => true,
+            _ => false,
+        }
+    }
+}
+
+/// With `std` feature errors can be propagated with `?` into `Box<dyn std::error::Error>`.
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for Error<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::I2c(e) => Some(e),
+            _ => None,
+        }
+    }
 }
 
 #[repr(u8)]
@@ -95,142 +234,2603 @@ pub enum Error<E> {
 #[derive(PartialEq)]
 enum Command {
     MeasureRhHoldMaster = 0xE5,
+    MeasureRhNoHoldMaster = 0xF5,
+    MeasureTemperatureHoldMaster = 0xE3,
+    MeasureTemperatureNoHoldMaster = 0xF3,
     ReadTemperatureFromRh = 0xE0,
+    ReadUserRegister1 = 0xE7,
+    WriteUserRegister1 = 0xE6,
+    ReadHeaterControl = 0x11,
+    WriteHeaterControl = 0x51,
+    MeasureThermistor = 0xEE,
+    ReadUserRegister2 = 0x10,
+    WriteUserRegister2 = 0x50,
+    ReadThermistorCoefficient = 0x84,
+    WriteThermistorCoefficient = 0xC5,
+    Reset = 0xFE,
+}
+
+/// Two byte command for reading firmware revision.
+const READ_FIRMWARE_REVISION: [u8; 2] = [0x84, 0xB8];
+
+/// Two byte command for reading the first half of the electronic serial number.
+const READ_ELECTRONIC_ID_1: [u8; 2] = [0xFA, 0x0F];
+
+/// Two byte command for reading the second half of the electronic serial number.
+const READ_ELECTRONIC_ID_2: [u8; 2] = [0xFC, 0xC9];
+
+/// Time from power-up until the sensor responds on the bus in milliseconds.
+const POWER_UP_TIME_MS: u32 = 80;
+
+/// Time from software reset until the sensor responds on the bus in milliseconds.
+const RESET_TIME_MS: u32 = 15;
+
+/// VDDS bit in User Register 1, set when supply voltage is low.
+const USER_REGISTER1_VDDS: u8 = 1 << 6;
+
+/// HTRE bit in User Register 1, set when the on-chip heater is enabled.
+const USER_REGISTER1_HTRE: u8 = 1 << 2;
+
+/// HEATER bits in Heater Control Register.
+const HEATER_CONTROL_MASK: u8 = 0x0F;
+
+/// Reserved bits 1, 3, 4 and 5 in User Register 1.
+const USER_REGISTER1_RESERVED_MASK: u8 = 0b0011_1010;
+
+/// RES1 and RES0 bits in User Register 1.
+const USER_REGISTER1_RES_MASK: u8 = 0x81;
+
+/// Interval between polling reads while waiting for a conversion in milliseconds.
+const POLL_INTERVAL_MS: u32 = 1;
+
+/// Minimum length of buffers passed to [`Si70xx::read_measurements_into`].
+pub const MIN_BUFFER_LEN: usize = 3;
+
+/// Time after which [`Si70xx::benchmark_conversion`] stops waiting in milliseconds.
+const BENCHMARK_TIMEOUT_MS: u64 = 100;
+
+/// Default margin added to conversion times in milliseconds.
+const DEFAULT_TIMING_MARGIN_MS: u32 = 1;
+
+/// How a measurement is started.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MeasurementMode {
+    /// Sensor holds the I²C bus by clock stretching until the conversion finishes.
+    HoldMaster,
+    /// Sensor releases the I²C bus during the conversion.
+    NoHoldMaster,
+}
+
+/// Sensor firmware revision.
+///
+/// Datasheet specifies the same relative humidity and temperature conversion for both
+/// revisions, therefore measurements are converted the same way regardless of the revision.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FirmwareRevision {
+    /// Firmware version 1.0, reported as 0xFF.
+    V1_0,
+    /// Firmware version 2.0, reported as 0x20.
+    V2_0,
+    /// Revision not known to this driver.
+    Other(u8),
+}
+
+impl From<u8> for FirmwareRevision {
+    fn from(value: u8) -> Self {
+        match value {
+            0xFF => FirmwareRevision::V1_0,
+            0x20 => FirmwareRevision::V2_0,
+            other => FirmwareRevision::Other(other),
+        }
+    }
+}
+
+/// Sensor model, as encoded in the SNB_3 byte of the electronic serial number.
+///
+/// Read it with [`Si70xx::read_device`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Device {
+    /// Si7006, reported as 0x06.
+    Si7006,
+    /// Si7013, reported as 0x0D.
+    Si7013,
+    /// Si7020, reported as 0x14.
+    Si7020,
+    /// Si7021, reported as 0x15.
+    Si7021,
+    /// Si7034, reported as 0x22.
+    Si7034,
+    /// Si7050 temperature-only sensor, reported as 0x32.
+    Si7050,
+    /// Si7051 temperature-only sensor, reported as 0x33.
+    Si7051,
+    /// Si7053 temperature-only sensor, reported as 0x35.
+    Si7053,
+    /// Si7054 temperature-only sensor, reported as 0x36.
+    Si7054,
+    /// Si7055 temperature-only sensor, reported as 0x37.
+    Si7055,
+    /// Engineering sample, reported as 0x00 or 0xFF.
+    EngineeringSample,
+    /// Model not known to this driver.
+    Unknown(u8),
+}
+
+impl From<u8> for Device {
+    fn from(value: u8) -> Self {
+        match value {
+            0x06 => Device::Si7006,
+            0x0D => Device::Si7013,
+            0x14 => Device::Si7020,
+            0x15 => Device::Si7021,
+            0x22 => Device::Si7034,
+            0x32 => Device::Si7050,
+            0x33 => Device::Si7051,
+            0x35 => Device::Si7053,
+            0x36 => Device::Si7054,
+            0x37 => Device::Si7055,
+            0x00 | 0xFF => Device::EngineeringSample,
+            other => Device::Unknown(other),
+        }
+    }
+}
+
+/// Identification data read with [`Si70xx::identify`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DeviceInfo {
+    /// 64-bit electronic serial number, most significant byte first.
+    pub serial_number: [u8; 8],
+    /// Device decoded from the serial number.
+    pub device: Device,
+    /// Firmware revision.
+    pub firmware_revision: FirmwareRevision,
+}
+
+/// Relative humidity and temperature measurement resolution.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    /// 12-bit relative humidity and 14-bit temperature, power-on default.
+    Rh12Temp14,
+    /// 8-bit relative humidity and 12-bit temperature.
+    Rh8Temp12,
+    /// 10-bit relative humidity and 13-bit temperature.
+    Rh10Temp13,
+    /// 11-bit relative humidity and 11-bit temperature.
+    Rh11Temp11,
+}
+
+impl Resolution {
+    /// RES1 (bit 7) and RES0 (bit 0) of User Register 1.
+    fn bits(self) -> u8 {
+        match self {
+            Resolution::Rh12Temp14 => 0x00,
+            Resolution::Rh8Temp12 => 0x01,
+            Resolution::Rh10Temp13 => 0x80,
+            Resolution::Rh11Temp11 => 0x81,
+        }
+    }
+
+    /// Decodes RES1 (bit 7) and RES0 (bit 0) of User Register 1, other bits are ignored.
+    fn from_bits(reg: u8) -> Self {
+        match reg & USER_REGISTER1_RES_MASK {
+            0x00 => Resolution::Rh12Temp14,
+            0x01 => Resolution::Rh8Temp12,
+            0x80 => Resolution::Rh10Temp13,
+            _ => Resolution::Rh11Temp11,
+        }
+    }
+
+    /// Worst case conversion time of a relative humidity measurement in milliseconds.
+    ///
+    /// A relative humidity measurement also converts temperature, so this is the sum
+    /// of maximum relative humidity and temperature conversion times rounded up.
+    fn rh_conversion_time_ms(self) -> u32 {
+        match self {
+            Resolution::Rh12Temp14 => 23,
+            Resolution::Rh8Temp12 => 7,
+            Resolution::Rh10Temp13 => 11,
+            Resolution::Rh11Temp11 => 10,
+        }
+    }
+
+    /// Worst case conversion time of a temperature measurement in milliseconds rounded up.
+    fn temperature_conversion_time_ms(self) -> u32 {
+        match self {
+            Resolution::Rh12Temp14 => 11,
+            Resolution::Rh8Temp12 => 4,
+            Resolution::Rh10Temp13 => 7,
+            Resolution::Rh11Temp11 => 3,
+        }
+    }
+}
+
+/// Worst case conversion time of a Si7013 thermistor measurement in milliseconds.
+///
+/// Thermistor is measured by the auxiliary ADC, its conversion time does not
+/// depend on relative humidity and temperature resolution.
+const THERMISTOR_CONVERSION_TIME_MS: u32 = 7;
+
+/// Die temperature where thermistor compensation is zero, in Celsius multiplied by 100.
+const THERMISTOR_REFERENCE_TEMPERATURE: i32 = 2500;
+
+/// Model converting Si7013 thermistor code to temperature.
+///
+/// Thermistor circuit is board specific, therefore the coefficients come from
+/// calibrating the board. Temperature in Celsius multiplied by 100 is
+///
+/// `offset + code * gain / 65536 + (die - 2500) * die_gain / 65536`
+///
+/// where `die` is on-die temperature in Celsius multiplied by 100. Last term compensates
+/// the drift of the bias circuit with board temperature, it is zero at 25°C.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ThermistorCoefficients {
+    /// Temperature at code 0 in Celsius multiplied by 100.
+    pub offset: i32,
+    /// Change in centi-degrees per code step, scaled by 65536.
+    pub gain: i32,
+    /// Change in centi-degrees per centi-degree of die temperature, scaled by 65536.
+    pub die_gain: i32,
+}
+
+impl ThermistorCoefficients {
+    /// Converts thermistor `code` to temperature using on-die `die_temperature`.
+    ///
+    /// Both temperatures are in Celsius multiplied by 100, result saturates at the `i16` range.
+    pub fn apply(&self, code: u16, die_temperature: i16) -> i16 {
+        let code_term = code as i64 * self.gain as i64;
+        let die_term = (die_temperature as i64 - THERMISTOR_REFERENCE_TEMPERATURE as i64)
+            * self.die_gain as i64;
+        let value = self.offset as i64 + ((code_term + die_term) >> 16);
+        value.clamp(i16::MIN as i64, i16::MAX as i64) as i16
+    }
+}
+
+/// Resolution configured by [`Si70xx::init`].
+///
+/// It is [`Resolution::Rh12Temp14`] by default and [`Resolution::Rh8Temp12`]
+/// if `low-resolution` feature is enabled. The fast low resolution mode suits battery
+/// powered builds because conversions are several times shorter.
+#[cfg(not(feature = "low-resolution"))]
+pub const DEFAULT_RESOLUTION: Resolution = Resolution::Rh12Temp14;
+/// Resolution configured by [`Si70xx::init`].
+///
+/// It is [`Resolution::Rh12Temp14`] by default and [`Resolution::Rh8Temp12`]
+/// if `low-resolution` feature is enabled. The fast low resolution mode suits battery
+/// powered builds because conversions are several times shorter.
+#[cfg(feature = "low-resolution")]
+pub const DEFAULT_RESOLUTION: Resolution = Resolution::Rh8Temp12;
+
+/// Supply voltage status reported by VDDS bit of User Register 1.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VddStatus {
+    /// Supply voltage is sufficient for operation.
+    Ok,
+    /// Supply voltage is below 1.9 V, measurements may be inaccurate.
+    Low,
+}
+
+/// On-chip heater current, typical values at 3.3 V supply.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u8)]
+pub enum HeaterCurrent {
+    /// 3.09 mA
+    Level0 = 0,
+    /// 9.18 mA
+    Level1 = 1,
+    /// 15.24 mA
+    Level2 = 2,
+    /// 21.31 mA
+    Level3 = 3,
+    /// 27.39 mA
+    Level4 = 4,
+    /// 33.46 mA
+    Level5 = 5,
+    /// 39.53 mA
+    Level6 = 6,
+    /// 45.61 mA
+    Level7 = 7,
+    /// 51.69 mA
+    Level8 = 8,
+    /// 57.76 mA
+    Level9 = 9,
+    /// 63.83 mA
+    Level10 = 10,
+    /// 69.90 mA
+    Level11 = 11,
+    /// 75.98 mA
+    Level12 = 12,
+    /// 82.05 mA
+    Level13 = 13,
+    /// 88.12 mA
+    Level14 = 14,
+    /// 94.20 mA
+    Level15 = 15,
+}
+
+impl HeaterCurrent {
+    /// Decodes HEATER bits of Heater Control Register, other bits are ignored.
+    fn from_bits(bits: u8) -> Self {
+        match bits & HEATER_CONTROL_MASK {
+            0 => HeaterCurrent::Level0,
+            1 => HeaterCurrent::Level1,
+            2 => HeaterCurrent::Level2,
+            3 => HeaterCurrent::Level3,
+            4 => HeaterCurrent::Level4,
+            5 => HeaterCurrent::Level5,
+            6 => HeaterCurrent::Level6,
+            7 => HeaterCurrent::Level7,
+            8 => HeaterCurrent::Level8,
+            9 => HeaterCurrent::Level9,
+            10 => HeaterCurrent::Level10,
+            11 => HeaterCurrent::Level11,
+            12 => HeaterCurrent::Level12,
+            13 => HeaterCurrent::Level13,
+            14 => HeaterCurrent::Level14,
+            _ => HeaterCurrent::Level15,
+        }
+    }
+}
+
+/// Behavior of the sensor at one resolution, see [`Si70xx::characterize`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ResolutionProfile {
+    /// Resolution the measurement was done with.
+    pub resolution: Resolution,
+    /// Raw relative humidity code.
+    pub rh_code: u16,
+    /// Raw temperature code from the same conversion.
+    pub temperature_code: u16,
+    /// Measured conversion time in milliseconds, rounded up to whole milliseconds.
+    pub conversion_time_ms: u32,
+}
+
+/// Result of [`Si70xx::characterize`], one profile per resolution.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Characterization {
+    /// Profiles in [`Resolution`] declaration order.
+    pub profiles: [ResolutionProfile; 4],
+}
+
+/// Sensor configuration applied by [`Si70xx::new_configured`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DeviceConfig {
+    /// I2C address of the sensor.
+    pub address: Address,
+    /// Relative humidity and temperature measurement resolution.
+    pub resolution: Resolution,
+    /// Whether the on-chip heater is enabled.
+    pub heater: bool,
+}
+
+impl Default for DeviceConfig {
+    /// Default address and [`DEFAULT_RESOLUTION`] with the heater disabled.
+    fn default() -> Self {
+        Self {
+            address: Address::H40,
+            resolution: DEFAULT_RESOLUTION,
+            heater: false,
+        }
+    }
 }
 
-pub struct Si70xx<I2C> {
+/// Byte order of measurement codes read from the sensor.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// MSB first as specified in the datasheet.
+    BigEndian,
+    /// LSB first, a workaround for hardware that swaps the bytes.
+    LittleEndian,
+}
+
+/// How conversions round relative humidity and temperature to hundredths.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum RoundingMode {
+    /// Towards zero.
+    Truncate,
+    /// To the nearest value, halfway cases are rounded up.
+    #[default]
+    Nearest,
+    /// Towards negative infinity.
+    Floor,
+    /// Towards positive infinity.
+    Ceil,
+}
+
+impl RoundingMode {
+    /// Divides `numerator` by a positive `denominator`.
+    fn divide(self, numerator: i64, denominator: i64) -> i64 {
+        match self {
+            RoundingMode::Truncate => numerator / denominator,
+            RoundingMode::Nearest => (2 * numerator + denominator).div_euclid(2 * denominator),
+            RoundingMode::Floor => numerator.div_euclid(denominator),
+            RoundingMode::Ceil => -(-numerator).div_euclid(denominator),
+        }
+    }
+}
+
+/// Relative humidity in percent multiplied by 100, clamped to 0..=100%.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Humidity(u16);
+
+impl Humidity {
+    /// Constructs humidity from a percentage multiplied by 100, values above 100% are clamped.
+    pub const fn from_centi(value: u16) -> Self {
+        if value > 10000 {
+            Self(10000)
+        } else {
+            Self(value)
+        }
+    }
+
+    /// Returns humidity as a percentage multiplied by 100, for example 4955 is 49.55%.
+    pub const fn centi(self) -> u16 {
+        self.0
+    }
+
+    /// Returns humidity as a percentage, for example 49.55 is 49.55%.
+    #[cfg(feature = "float")]
+    pub fn as_f32(self) -> f32 {
+        self.0 as f32 / 100.
+    }
+}
+
+/// Temperature in Celsius multiplied by 100.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Temperature(i16);
+
+impl Temperature {
+    /// Constructs temperature from Celsius multiplied by 100.
+    pub const fn from_centi(value: i16) -> Self {
+        Self(value)
+    }
+
+    /// Returns temperature in Celsius multiplied by 100, for example 2550 is 25.50°C.
+    pub const fn centi(self) -> i16 {
+        self.0
+    }
+
+    /// Returns temperature in Celsius, for example 25.5 is 25.50°C.
+    #[cfg(feature = "float")]
+    pub fn as_f32(self) -> f32 {
+        self.0 as f32 / 100.
+    }
+}
+
+/// Relative humidity and temperature from a single conversion.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Measurement {
+    /// Relative humidity as a percentage multiplied by 100.
+    pub humidity: u16,
+    /// Temperature in Celsius multiplied by 100.
+    pub temperature: i16,
+}
+
+impl Measurement {
+    /// Returns relative humidity as [`Humidity`], clamped to 0..=100%.
+    pub fn humidity(&self) -> Humidity {
+        Humidity::from_centi(self.humidity)
+    }
+
+    /// Returns temperature as [`Temperature`].
+    pub fn temperature(&self) -> Temperature {
+        Temperature::from_centi(self.temperature)
+    }
+
+    /// Returns relative humidity and temperature change from `other` to `self`.
+    ///
+    /// Both differences are in the same units as the measurement, that is multiplied by 100.
+    /// Differences that do not fit into `i16` saturate.
+    pub fn delta(&self, other: &Measurement) -> (i16, i16) {
+        let rh = self.humidity as i32 - other.humidity as i32;
+        let temp = self.temperature as i32 - other.temperature as i32;
+        (saturate_i16(rh), saturate_i16(temp))
+    }
+
+    /// Returns lowest and highest relative humidity within the specified accuracy of the reading.
+    ///
+    /// Range is the reading ± [`humidity_accuracy_centi`], limited to 0..100 %RH. A reading
+    /// above 100 %RH is treated as 100 %RH. Both bounds are percentages multiplied by 100.
+    pub fn humidity_range(&self) -> (u16, u16) {
+        let humidity = self.humidity.min(10000);
+        let accuracy = humidity_accuracy_centi(humidity);
+        (
+            humidity.saturating_sub(accuracy),
+            (humidity + accuracy).min(10000),
+        )
+    }
+
+    /// Returns dew point in Celsius multiplied by 100.
+    ///
+    /// Calculated with the Magnus formula, which is accurate to about 0.1°C from -45 to 60°C.
+    /// Relative humidity below 0.01% is treated as 0.01%.
+    pub fn dew_point(&self) -> i16 {
+        const B: f32 = 17.62;
+        const C: f32 = 243.12;
+        let rh = self.humidity.clamp(1, 10000) as f32 / 10000.;
+        let t = self.temperature as f32 / 100.;
+        let gamma = ln(rh) + B * t / (C + t);
+        saturate_i16((C * gamma / (B - gamma) * 100.) as i32)
+    }
+
+    /// Returns absolute humidity in grams per cubic meter multiplied by 100.
+    ///
+    /// Saturation vapour pressure is calculated with the same Magnus formula as [`dew_point`],
+    /// result saturates at the `u16` range.
+    ///
+    /// [`dew_point`]: Measurement::dew_point
+    pub fn absolute_humidity(&self) -> u16 {
+        const B: f32 = 17.62;
+        const C: f32 = 243.12;
+        let rh = self.humidity.min(10000) as f32 / 10000.;
+        let t = self.temperature as f32 / 100.;
+        // Saturation vapour pressure in hPa.
+        let es = 6.112 * exp(B * t / (C + t));
+        let value = 216.7 * rh * es / (273.15 + t) * 100.;
+        value.clamp(0., u16::MAX as f32) as u16
+    }
+
+    /// Returns whether temperature is within [`DEFAULT_CONDENSATION_MARGIN`] of the dew point.
+    pub fn condensation_risk(&self) -> bool {
+        self.condensation_risk_within(DEFAULT_CONDENSATION_MARGIN)
+    }
+
+    /// Returns whether temperature is within `margin` of the dew point.
+    ///
+    /// `margin` is in Celsius multiplied by 100.
+    pub fn condensation_risk_within(&self, margin: u16) -> bool {
+        self.temperature as i32 - self.dew_point() as i32 <= margin as i32
+    }
+
+    /// Formats the measurement as text, for example `52.3%RH 24.81C`.
+    ///
+    /// Relative humidity is rounded to one decimal. Capacity is enough for any measurement,
+    /// the longest possible text is `655.4%RH -327.68C`.
+    #[cfg(feature = "heapless")]
+    pub fn to_string(&self) -> heapless::String<32> {
+        use core::fmt::Write;
+
+        let rh = (self.humidity as u32 + 5) / 10;
+        let temp = self.temperature as i32;
+        let sign = if temp < 0 { "-" } else { "" };
+        let temp = temp.unsigned_abs();
+        let mut text = heapless::String::new();
+        // Can not fail, the longest text fits into the capacity.
+        let _ = write!(
+            text,
+            "{}.{}%RH {}{}.{:02}C",
+            rh / 10,
+            rh % 10,
+            sign,
+            temp / 100,
+            temp % 100
+        );
+        text
+    }
+}
+
+/// Margin used by [`Measurement::condensation_risk`], 2°C.
+pub const DEFAULT_CONDENSATION_MARGIN: u16 = 200;
+
+/// Natural logarithm of a positive `x`, accurate to about 1e-5.
+fn ln(x: f32) -> f32 {
+    // x = m * 2^e where m is in 1..2
+    let bits = x.to_bits();
+    let e = ((bits >> 23) & 0xFF) as i32 - 127;
+    let m = f32::from_bits((bits & 0x007F_FFFF) | 0x3F80_0000);
+    // ln(m) = 2 * atanh((m - 1) / (m + 1))
+    let s = (m - 1.) / (m + 1.);
+    let s2 = s * s;
+    let ln_m = 2. * s * (1. + s2 * (1. / 3. + s2 * (1. / 5. + s2 * (1. / 7. + s2 / 9.))));
+    e as f32 * core::f32::consts::LN_2 + ln_m
+}
+
+/// Exponential of `x` in the range of about -80..80, accurate to about 1e-6.
+fn exp(x: f32) -> f32 {
+    // e^x = 2^k * e^r where |r| <= ln(2) / 2
+    let y = x * core::f32::consts::LOG2_E;
+    let k = if y < 0. { y - 0.5 } else { y + 0.5 } as i32;
+    let r = x - k as f32 * core::f32::consts::LN_2;
+    let e_r =
+        1. + r * (1. + r * (1. / 2. + r * (1. / 6. + r * (1. / 24. + r * (1. / 120. + r / 720.)))));
+    f32::from_bits(((k + 127) as u32) << 23) * e_r
+}
+
+fn saturate_i16(value: i32) -> i16 {
+    value.clamp(i16::MIN as i32, i16::MAX as i32) as i16
+}
+
+/// Monotonic time source in milliseconds.
+///
+/// Implemented for closures, for example `|| timer.now().ticks() as u64`.
+pub trait Clock {
+    /// Returns current time in milliseconds.
+    fn now_ms(&mut self) -> u64;
+}
+
+impl<F: FnMut() -> u64> Clock for F {
+    fn now_ms(&mut self) -> u64 {
+        self()
+    }
+}
+
+/// Measurement started with [`Si70xx::start`] that can be read out with [`Si70xx::finish`].
+///
+/// The handle does not borrow the driver, so other work can be done during the conversion.
+/// It refers to the last conversion started on the driver that created it. Starting another
+/// measurement on that driver before finishing this one reads out the newer conversion.
+#[derive(Debug)]
+pub struct PendingMeasurement {
+    ready_at_ms: u64,
+}
+
+impl PendingMeasurement {
+    /// Returns time in milliseconds after which the conversion has finished.
+    pub fn ready_at_ms(&self) -> u64 {
+        self.ready_at_ms
+    }
+
+    /// Checks whether the conversion has finished.
+    pub fn is_ready<C: Clock>(&self, clock: &mut C) -> bool {
+        clock.now_ms() >= self.ready_at_ms
+    }
+}
+
+/// Blocking Si70xx driver.
+pub type Si70xx<I2C, H = Hooks> = Driver<I2C, Blocking, H>;
+
+/// Async Si70xx driver.
+#[cfg(feature = "async")]
+pub type AsyncSi70xx<I2C, H = Hooks> = Driver<I2C, Async, H>;
+
+/// Callbacks set on the driver, see [`Si70xx::with_bus_clear_check`], [`Si70xx::with_on_success`],
+/// [`Si70xx::with_before_measure`] and [`Si70xx::with_after_read`].
+///
+/// Each callback has its own type parameter, so closures that capture state, for example
+/// a watchdog or GPIO handle, can be used and a callback that is not set costs nothing.
+/// Default parameters only name the type of callbacks that have not been set.
+pub struct Hooks<B = fn() -> bool, S = fn(), BM = fn(), AR = fn(&Measurement)> {
+    bus_clear_check: Option<B>,
+    on_success: Option<S>,
+    before_measure: Option<BM>,
+    after_read: Option<AR>,
+}
+
+/// Calls the callbacks stored in [`Hooks`], the driver requires it from its hooks type.
+pub trait DriverHooks {
+    /// Returns whether the bus is idle, `true` if no check is set.
+    fn bus_idle(&mut self) -> bool;
+
+    /// Called right before a conversion is started.
+    fn before_measure(&mut self);
+
+    /// Called with each successfully read measurement.
+    fn after_read(&mut self, measurement: &Measurement);
+
+    /// Called after each successful read, after [`after_read`](DriverHooks::after_read).
+    fn on_success(&mut self);
+}
+
+impl<B, S, BM, AR> DriverHooks for Hooks<B, S, BM, AR>
+where
+    B: FnMut() -> bool,
+    S: FnMut(),
+    BM: FnMut(),
+    AR: FnMut(&Measurement),
+{
+    fn bus_idle(&mut self) -> bool {
+        match &mut self.bus_clear_check {
+            Some(bus_idle) => bus_idle(),
+            None => true,
+        }
+    }
+
+    fn before_measure(&mut self) {
+        if let Some(before_measure) = &mut self.before_measure {
+            before_measure();
+        }
+    }
+
+    fn after_read(&mut self, measurement: &Measurement) {
+        if let Some(after_read) = &mut self.after_read {
+            after_read(measurement);
+        }
+    }
+
+    fn on_success(&mut self) {
+        if let Some(on_success) = &mut self.on_success {
+            on_success();
+        }
+    }
+}
+
+/// Marker selecting the blocking API of [`Driver`].
+#[derive(Debug)]
+pub enum Blocking {}
+
+/// Marker selecting the async API of [`Driver`].
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub enum Async {}
+
+/// Si70xx driver, use [`Si70xx`] alias for the blocking API or `AsyncSi70xx` for the async one.
+///
+/// Both APIs are available at the same time when `async` feature is enabled.
+/// `H` holds the callbacks set with the `with_*` hook builders, see [`Hooks`].
+pub struct Driver<I2C, M, H = Hooks> {
     i2c: I2C,
     addr: u8,
+    last: Option<Measurement>,
+    timing_margin_ms: u32,
+    supply_check: bool,
+    pending: Option<MeasurementMode>,
+    byte_order: ByteOrder,
+    resolution: Resolution,
+    hooks: H,
+    rounding: RoundingMode,
+    vdd_low: bool,
+    crc: bool,
+    temperature_crc: bool,
+    heater: bool,
+    heater_current: HeaterCurrent,
+    auto_reset_after: Option<u32>,
+    crc_failures: u32,
+    mode: PhantomData<M>,
+}
+
+/// Calculates CRC-8 checksum of measurement and electronic serial number bytes.
+///
+/// Polynomial is x⁸+x⁵+x⁴+1 (0x131) and initialization value is 0x00.
+/// Checksum of a measurement frame is calculated over its first two bytes,
+/// the result should match the third byte.
+pub fn crc8(data: &[u8]) -> u8 {
+    crc8_const(data)
+}
+
+/// Returns whether the third byte of a measurement frame is the checksum of the first two.
+fn crc_valid(frame: &[u8; 3]) -> bool {
+    crc8(&frame[..2]) == frame[2]
+}
+
+/// Validates both parts of the electronic serial number and joins them.
+fn serial_number<E>(sna: [u8; 8], snb: [u8; 6]) -> Result<[u8; 8], Error<E>> {
+    let sna_valid = sna.chunks(2).all(|pair| crc8(&pair[..1]) == pair[1]);
+    let snb_valid = snb.chunks(3).all(|word| crc8(&word[..2]) == word[2]);
+    if !sna_valid || !snb_valid {
+        return Err(Error::Crc(Frame::SerialNumber));
+    }
+    Ok([
+        sna[0], sna[2], sna[4], sna[6], snb[0], snb[1], snb[3], snb[4],
+    ])
+}
+
+/// Calculates the same checksum as [`crc8`] in a `const` context.
+///
+/// Useful for computing expected checksums of known frames at compile time.
+pub const fn crc8_const(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    let mut i = 0;
+    while i < data.len() {
+        crc ^= data[i];
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x31
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        i += 1;
+    }
+    crc
+}
+
+/// Returns approximate energy of one relative humidity and temperature measurement in microjoules.
+///
+/// Calculated from typical conversion times and typical supply current during conversion
+/// in the Si7021 datasheet, 150 µA for relative humidity and 90 µA for temperature, at 3.3 V.
+/// The result is rounded up. Energy used by the I²C transfers and in standby is not included.
+pub fn energy_per_sample_uj(res: Resolution) -> u32 {
+    match res {
+        // 10 ms RH + 7 ms temperature, 7.0 µJ
+        Resolution::Rh12Temp14 => 8,
+        // 2.6 ms RH + 2.4 ms temperature, 2.0 µJ
+        Resolution::Rh8Temp12 => 2,
+        // 3.7 ms RH + 4 ms temperature, 3.0 µJ
+        Resolution::Rh10Temp13 => 4,
+        // 5.8 ms RH + 1.5 ms temperature, 3.3 µJ
+        Resolution::Rh11Temp11 => 4,
+    }
 }
 
-impl<I2C, E> Si70xx<I2C>
+/// Returns maximum relative humidity error at the given reading.
+///
+/// Both reading and result are percentages multiplied by 100. The accuracy is ±3 %RH
+/// from 0 to 80 %RH and degrades linearly to ±5 %RH at 100 %RH, which approximates
+/// the maximum accuracy curve in the Si7021 datasheet.
+///
+/// The curve is specific to Si7021 and the same values are returned for every part.
+/// Si7006 and Si7020 specify a wider tolerance, see their datasheets.
+pub fn humidity_accuracy_centi(rh_centi: u16) -> u16 {
+    let rh = rh_centi.min(10000);
+    if rh <= 8000 {
+        300
+    } else {
+        300 + (rh - 8000) / 10
+    }
+}
+
+/// Returns maximum temperature error at the given reading.
+///
+/// Both reading and result are in Celsius multiplied by 100. The accuracy is ±0.4°C
+/// from -10 to 85°C and degrades linearly to ±0.7°C at -40°C and 125°C, which approximates
+/// the maximum accuracy curve in the Si7021 datasheet.
+///
+/// The curve is specific to Si7021 and the same values are returned for every part.
+/// Si7006 specifies a wider tolerance, see its datasheet.
+pub fn temperature_accuracy_centi(temp_centi: i16) -> u16 {
+    let temp = temp_centi.clamp(-4000, 12500) as i32;
+    let error = if temp > 8500 {
+        40 + (temp - 8500) * 30 / 4000
+    } else if temp < -1000 {
+        40 + (-1000 - temp) * 30 / 3000
+    } else {
+        40
+    };
+    error as u16
+}
+
+/// Sensor operating limits from the datasheet.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct OperatingLimits {
+    /// Minimum operating temperature in Celsius multiplied by 100.
+    pub temperature_min: i16,
+    /// Maximum operating temperature in Celsius multiplied by 100.
+    pub temperature_max: i16,
+    /// Minimum relative humidity as a percentage multiplied by 100.
+    pub humidity_min: u16,
+    /// Maximum relative humidity as a percentage multiplied by 100.
+    pub humidity_max: u16,
+    /// Minimum supply voltage in millivolts.
+    pub supply_min_mv: u16,
+    /// Maximum supply voltage in millivolts.
+    pub supply_max_mv: u16,
+}
+
+/// Returns operating limits of Si7006/13/20/21 sensors.
+///
+/// Temperature range is -40..125°C, relative humidity range is 0..100 %RH
+/// and supply voltage range is 1.9..3.6 V.
+pub const fn operating_limits() -> OperatingLimits {
+    OperatingLimits {
+        temperature_min: -4000,
+        temperature_max: 12500,
+        humidity_min: 0,
+        humidity_max: 10000,
+        supply_min_mv: 1900,
+        supply_max_mv: 3600,
+    }
+}
+
+/// Relative humidity response time (τ63) in milliseconds.
+///
+/// After a step change it takes about this long for a reading to cover 63% of the change.
+/// Allow several time constants before expecting a reading to settle.
+pub const RH_RESPONSE_TIME_MS: u32 = 18000;
+
+/// Checks whether relative humidity readings have settled.
+///
+/// Returns `true` if there are at least two samples and all of them are within
+/// `threshold` of each other. Samples and threshold are percentages multiplied by 100,
+/// as returned by [`Si70xx::read_humidity`].
+pub fn settling_reached(samples: &[u16], threshold: u16) -> bool {
+    if samples.len() < 2 {
+        return false;
+    }
+    let min = samples.iter().min().copied().unwrap_or_default();
+    let max = samples.iter().max().copied().unwrap_or_default();
+    max - min <= threshold
+}
+
+/// Convert relative humidity code to percentage multiplied by 100, clamped to 0..=100%.
+fn humidity_from_code(rh_code: u16, rounding: RoundingMode) -> u16 {
+    humidity_from_code_unclamped(rh_code, rounding).clamp(0, 10000) as u16
+}
+
+/// Convert relative humidity code to percentage multiplied by 100 without limiting the result.
+///
+/// Codes near the ends of the range convert to values below 0% or above 100%.
+fn humidity_from_code_unclamped(rh_code: u16, rounding: RoundingMode) -> i32 {
+    rounding.divide(12500 * rh_code as i64 - 600 * 65536, 65536) as i32
+}
+
+/// Convert temperature code to Celsius multiplied by 100.
+fn temperature_from_code(temp_code: u16, rounding: RoundingMode) -> i16 {
+    rounding.divide(17572 * temp_code as i64 - 4685 * 65536, 65536) as i16
+}
+
+impl<I2C, E, M> Driver<I2C, M>
 where
-    I2C: I2c<Error = E>,
+    I2C: ErrorType<Error = E>,
 {
-    /// Construct new Si70xx sensor.
-    #[cfg(not(feature = "si7013"))]
+    /// Construct new Si70xx sensor using the default 0x40 address.
     pub fn new(i2c: I2C) -> Self {
-        Self {
-            i2c,
-            addr: I2C_ADDR,
-        }
+        Self::new_with_address(i2c, Address::H40)
     }
 
-    /// Construct new Si7013 sensor.
-    #[cfg(feature = "si7013")]
-    pub fn new(i2c: I2C, addr: Address) -> Self {
+    /// Construct new Si70xx sensor using `addr`, Si7013 can be strapped to 0x41.
+    pub fn new_with_address(i2c: I2C, addr: Address) -> Self {
         Self {
             i2c,
             addr: addr as u8,
+            last: None,
+            timing_margin_ms: DEFAULT_TIMING_MARGIN_MS,
+            supply_check: false,
+            pending: None,
+            byte_order: ByteOrder::BigEndian,
+            resolution: Resolution::Rh12Temp14,
+            hooks: Hooks {
+                bus_clear_check: None,
+                on_success: None,
+                before_measure: None,
+                after_read: None,
+            },
+            rounding: RoundingMode::Nearest,
+            vdd_low: false,
+            crc: true,
+            temperature_crc: false,
+            heater: false,
+            heater_current: HeaterCurrent::Level0,
+            auto_reset_after: None,
+            crc_failures: 0,
+            mode: PhantomData,
         }
     }
+}
 
-    /// Initiates a measurement for relative humidity and temperature.
+impl<I2C, E, M, H> Driver<I2C, M, H>
+where
+    I2C: ErrorType<Error = E>,
+    H: DriverHooks,
+{
+    /// Sets the safety margin added to conversion times when waiting with a delay.
     ///
-    /// This method starts both the relative humidity and temperature measurement.
-    /// Use [`read_humidity`] and [`read_temperature`] to retrieve the measurements.
+    /// Methods that wait for a conversion using a delay, such as [`read_measurements`],
+    /// wait for the datasheet maximum conversion time plus this margin.
+    /// The default is 1 ms. Increase it if the delay implementation is coarse or
+    /// may return early, otherwise the result could be read before the conversion finishes.
     ///
-    /// [`read_humidity`]: Si70xx::read_humidity
-    /// [`read_temperature`]: Si70xx::read_temperature
-    #[cfg(not(feature = "async"))]
-    pub fn measure(&mut self) -> Result<(), Error<E>> {
-        self.i2c
-            .write(self.addr, &[Command::MeasureRhHoldMaster as u8])
-            .map_err(Error::I2c)?;
-        Ok(())
+    /// [`read_measurements`]: Si70xx::read_measurements
+    pub fn with_timing_margin_ms(mut self, margin_ms: u32) -> Self {
+        self.timing_margin_ms = margin_ms;
+        self
     }
 
-    /// Initiates a measurement for relative humidity and temperature.
+    /// Enables checking supply voltage before each measurement.
     ///
-    /// This method starts both the relative humidity and temperature measurement.
-    /// Use [`read_humidity`] and [`read_temperature`] to retrieve the measurements.
+    /// When enabled, methods that start a conversion, such as [`measure`] and
+    /// [`read_measurements`], read the VDDS bit of User Register 1 first and return
+    /// [`Error::LowSupply`] without starting a conversion if the supply voltage is below
+    /// the operating threshold. Readings would be unreliable anyway, so during brownout
+    /// the sample cycle can be skipped. Disabled by default.
     ///
-    /// [`read_humidity`]: Si70xx::read_humidity
-    /// [`read_temperature`]: Si70xx::read_temperature
-    #[cfg(feature = "async")]
-    pub async fn measure(&mut self) -> Result<(), Error<E>> {
-        self.i2c
-            .write(self.addr, &[Command::MeasureRhHoldMaster as u8])
-            .await
-            .map_err(Error::I2c)?;
-        Ok(())
+    /// [`measure`]: Si70xx::measure
+    /// [`read_measurements`]: Si70xx::read_measurements
+    pub fn with_supply_check(mut self, enabled: bool) -> Self {
+        self.supply_check = enabled;
+        self
     }
 
-    /// Retrieves the last measured relative humidity.
+    /// Sets how relative humidity and temperature are rounded to hundredths.
     ///
-    /// This method should be called after [`measure`].
-    /// It returns the relative humidity as a percentage multiplied by 100.
-    /// For example, a return value of 4955 represents 49.55%.
+    /// Default is [`RoundingMode::Nearest`], which has the least bias.
+    pub fn with_rounding_mode(mut self, rounding: RoundingMode) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// Enables or disables checksum validation of measurement frames.
     ///
-    /// [`measure`]: Si70xx::measure
-    #[cfg(not(feature = "async"))]
-    pub fn read_humidity(&mut self) -> Result<u16, Error<E>> {
-        let mut response = [0u8; 2];
-        self.i2c
-            .read(self.addr, &mut response)
-            .map_err(Error::I2c)?;
-        let rh_code = (u16::from_be_bytes([response[0], response[1]])) as u32;
-        Ok(((12500 * rh_code) / 65536 - 600) as u16)
+    /// The sensor sends a checksum byte after each measurement code. It is read and validated by
+    /// default and a corrupted frame is reported as [`Error::Crc`] naming the frame, which helps
+    /// to tell apart timing problems and bus noise. Disabling it keeps the shorter 2-byte
+    /// transfer and trusts the data. Serial number and raw frames are not affected.
+    pub fn with_crc(mut self, enabled: bool) -> Self {
+        self.crc = enabled;
+        self
     }
 
-    /// Retrieves the last measured relative humidity.
+    /// Enables checksum validation of the temperature read by [`read_measurements`] and
+    /// [`read_measurements_into`].
     ///
-    /// This method should be called after [`measure`].
-    /// It returns the relative humidity as a percentage multiplied by 100.
-    /// For example, a return value of 4955 represents 49.55%.
+    /// Temperature read out from the previous relative humidity measurement has no checksum,
+    /// therefore with validation enabled temperature is measured with a separate conversion,
+    /// which takes longer. Has no effect if checksums are disabled with [`with_crc`].
+    /// Disabled by default.
     ///
-    /// [`measure`]: Si70xx::measure
-    #[cfg(feature = "async")]
-    pub async fn read_humidity(&mut self) -> Result<u16, Error<E>> {
-        let mut response = [0u8; 2];
-        self.i2c
-            .read(self.addr, &mut response)
-            .await
-            .map_err(Error::I2c)?;
-        let rh_code = (u16::from_be_bytes([response[0], response[1]])) as u32;
-        Ok(((12500 * rh_code) / 65536 - 600) as u16)
+    /// [`read_measurements`]: Si70xx::read_measurements
+    /// [`read_measurements_into`]: Si70xx::read_measurements_into
+    /// [`with_crc`]: Si70xx::with_crc
+    pub fn with_temperature_crc(mut self, enabled: bool) -> Self {
+        self.temperature_crc = enabled;
+        self
     }
 
-    /// Retrieves the last measured temperature.
+    /// Resets the sensor after `threshold` consecutive checksum failures.
     ///
-    /// This method should be called after [`measure`].
-    /// It returns the emperature in Celsius, multiplied by 100.
-    /// For example, a return value of 2550 represents 25.50°C.
+    /// Persistent [`Error::Crc`] failures often mean that the sensor is in a bad state that
+    /// a reset clears. Once `threshold` frames in a row have failed the check, the next method
+    /// that takes a `delay`, such as [`read_measurements`], first resets the sensor the same
+    /// way as [`recover`] and clears the counter. A frame with a valid checksum clears it too.
     ///
-    /// [`measure`]: Si70xx::measure
-    #[cfg(not(feature = "async"))]
-    pub fn read_temperature(&mut self) -> Result<i16, Error<E>> {
-        let mut response = [0u8; 2];
-        self.i2c
-            .write_read(
-                self.addr,
-                &[Command::ReadTemperatureFromRh as u8],
-                &mut response,
-            )
-            .map_err(Error::I2c)?;
-        let temp_code = (u16::from_be_bytes([response[0], response[1]])) as u32;
-        Ok(((17572 * temp_code) / 65536 - 4685) as i16)
-    }
-
-    /// Retrieves the last measured temperature.
-    ///
-    /// This method should be called after [`measure`].
-    /// It returns the emperature in Celsius, multiplied by 100.
-    /// For example, a return value of 2550 represents 25.50°C.
+    /// The driver does not store a delay while the reset needs one to wait for the sensor to
+    /// start up, therefore methods without a `delay` parameter only count failures. `None`
+    /// disables it, which is the default, and `Some(0)` is treated as `Some(1)`.
+    ///
+    /// [`read_measurements`]: Si70xx::read_measurements
+    /// [`recover`]: Si70xx::recover
+    pub fn with_auto_reset_after(mut self, threshold: Option<u32>) -> Self {
+        self.auto_reset_after = threshold;
+        self
+    }
+
+    /// Sets byte order used for decoding measurement codes.
+    ///
+    /// The sensor always sends MSB first and the default [`ByteOrder::BigEndian`] should be used.
+    /// [`ByteOrder::LittleEndian`] is only a workaround for boards with level shifters or
+    /// unusual wiring that have been seen to return byte swapped readings.
+    /// Raw frames from [`read_raw_frames`] are not affected.
+    ///
+    /// [`read_raw_frames`]: Si70xx::read_raw_frames
+    pub fn with_byte_order(mut self, byte_order: ByteOrder) -> Self {
+        self.byte_order = byte_order;
+        self
+    }
+
+    /// Returns the mode of the measurement that has been started but not yet read out.
+    ///
+    /// For example after [`measure`] this returns [`MeasurementMode::HoldMaster`] until
+    /// [`read_humidity`] has retrieved the result. If reading out fails or an async
+    /// measurement is cancelled, the conversion stays pending. This does not access the I²C bus.
     ///
     /// [`measure`]: Si70xx::measure
-    #[cfg(feature = "async")]
-    pub async fn read_temperature(&mut self) -> Result<i16, Error<E>> {
-        let mut response = [0u8; 2];
-        self.i2c
-            .write_read(
-                self.addr,
-                &[Command::ReadTemperatureFromRh as u8],
-                &mut response,
-            )
-            .await
-            .map_err(Error::I2c)?;
-        let temp_code = (u16::from_be_bytes([response[0], response[1]])) as u32;
-        Ok(((17572 * temp_code) / 65536 - 4685) as i16)
+    /// [`read_humidity`]: Si70xx::read_humidity
+    pub fn pending_command(&self) -> Option<MeasurementMode> {
+        self.pending
+    }
+
+    /// Returns relative humidity from the last successful [`read_measurements`].
+    ///
+    /// This does not access the I²C bus. `None` is returned if nothing has been read yet.
+    ///
+    /// [`read_measurements`]: Si70xx::read_measurements
+    pub fn last_humidity(&self) -> Option<u16> {
+        self.last.map(|m| m.humidity)
+    }
+
+    /// Returns temperature from the last successful [`read_measurements`].
+    ///
+    /// This does not access the I²C bus. `None` is returned if nothing has been read yet.
+    ///
+    /// [`read_measurements`]: Si70xx::read_measurements
+    pub fn last_temperature(&self) -> Option<i16> {
+        self.last.map(|m| m.temperature)
+    }
+
+    /// Returns whether low supply voltage has been seen since construction or [`clear_vdd_latch`].
+    ///
+    /// Sensor VDDS bit only shows the current state, this latch stays set after the supply has
+    /// recovered. It is updated whenever the driver reads User Register 1, for example before each
+    /// measurement with [`with_supply_check`] enabled. This does not access the I²C bus.
+    ///
+    /// [`clear_vdd_latch`]: Si70xx::clear_vdd_latch
+    /// [`with_supply_check`]: Si70xx::with_supply_check
+    pub fn vdd_low_since_reset(&self) -> bool {
+        self.vdd_low
+    }
+
+    /// Clears the latch returned by [`vdd_low_since_reset`].
+    ///
+    /// [`vdd_low_since_reset`]: Si70xx::vdd_low_since_reset
+    pub fn clear_vdd_latch(&mut self) {
+        self.vdd_low = false;
+    }
+
+    /// Keeps `measurement` as the last reading and calls the hooks set on the driver.
+    fn complete(&mut self, measurement: Measurement) -> Measurement {
+        self.last = Some(measurement);
+        self.hooks.after_read(&measurement);
+        self.hooks.on_success();
+        measurement
+    }
+
+    /// Decodes measurement code according to the configured byte order.
+    ///
+    /// Returns [`Error::InvalidData`] if all bits are equal, which indicates a bus fault.
+    fn code(&self, bytes: [u8; 2]) -> Result<u16, Error<E>> {
+        if bytes == [0x00, 0x00] || bytes == [0xFF, 0xFF] {
+            return Err(Error::InvalidData);
+        }
+        Ok(match self.byte_order {
+            ByteOrder::BigEndian => u16::from_be_bytes(bytes),
+            ByteOrder::LittleEndian => u16::from_le_bytes(bytes),
+        })
+    }
+
+    /// Number of bytes read out from a measurement frame, the checksum is skipped if disabled.
+    fn frame_len(&self) -> usize {
+        if self.crc {
+            3
+        } else {
+            2
+        }
+    }
+
+    /// Validates checksum of a measurement `frame` if enabled and returns its code.
+    ///
+    /// Consecutive checksum failures are counted for [`Si70xx::with_auto_reset_after`].
+    fn checked(&mut self, response: [u8; 3], frame: Frame) -> Result<u16, Error<E>> {
+        if self.crc {
+            if !crc_valid(&response) {
+                self.crc_failures = self.crc_failures.saturating_add(1);
+                return Err(Error::Crc(frame));
+            }
+            self.crc_failures = 0;
+        }
+        self.code([response[0], response[1]])
+    }
+
+    /// Returns whether enough checksum failures have been seen to reset the sensor.
+    fn auto_reset_due(&self) -> bool {
+        matches!(self.auto_reset_after, Some(threshold) if self.crc_failures >= threshold.max(1))
+    }
+}
+
+impl<I2C, M, B, S, BM, AR> Driver<I2C, M, Hooks<B, S, BM, AR>> {
+    /// Sets a check that is called before each measurement to verify that the bus is idle.
+    ///
+    /// Some HALs can report SDA and SCL line state. `bus_idle` should return `true` if both
+    /// lines are high. Otherwise measurement is not started and [`Error::BusStuck`] is returned,
+    /// which is easier to act on than a NACK deep in the read out, for example when another
+    /// device holds SDA low. `bus_idle` can be a closure that borrows or owns the pins.
+    pub fn with_bus_clear_check<F>(self, bus_idle: F) -> Driver<I2C, M, Hooks<F, S, BM, AR>>
+    where
+        F: FnMut() -> bool,
+    {
+        self.map_hooks(|hooks| Hooks {
+            bus_clear_check: Some(bus_idle),
+            on_success: hooks.on_success,
+            before_measure: hooks.before_measure,
+            after_read: hooks.after_read,
+        })
+    }
+
+    /// Sets a callback that is called after each successful [`read_measurements`].
+    ///
+    /// Also called when [`finish`] or other methods returning a [`Measurement`] succeed.
+    /// Intended for petting a watchdog, so that the watchdog fires if the sensor loop hangs
+    /// or keeps failing. `on_success` can be a closure that owns the watchdog handle.
+    /// The callback is called from inside the driver and must not block.
+    ///
+    /// [`read_measurements`]: Si70xx::read_measurements
+    /// [`finish`]: Si70xx::finish
+    pub fn with_on_success<F>(self, on_success: F) -> Driver<I2C, M, Hooks<B, F, BM, AR>>
+    where
+        F: FnMut(),
+    {
+        self.map_hooks(|hooks| Hooks {
+            bus_clear_check: hooks.bus_clear_check,
+            on_success: Some(on_success),
+            before_measure: hooks.before_measure,
+            after_read: hooks.after_read,
+        })
+    }
+
+    /// Sets a hook that is called right before each conversion is started.
+    ///
+    /// Called for every conversion the driver starts, after the checks enabled on the driver
+    /// have passed, for example to turn on an LED or record a timestamp. Hooks run inside
+    /// the driver call and must not use the driver.
+    pub fn with_before_measure<F>(self, before_measure: F) -> Driver<I2C, M, Hooks<B, S, F, AR>>
+    where
+        F: FnMut(),
+    {
+        self.map_hooks(|hooks| Hooks {
+            bus_clear_check: hooks.bus_clear_check,
+            on_success: hooks.on_success,
+            before_measure: Some(before_measure),
+            after_read: hooks.after_read,
+        })
+    }
+
+    /// Sets a hook that is called with each successfully read [`Measurement`].
+    ///
+    /// Called by [`read_measurements`], [`finish`] and other methods returning a
+    /// [`Measurement`], before the [`with_on_success`] callback. Hooks run inside the
+    /// driver call and must not use the driver.
+    ///
+    /// [`read_measurements`]: Si70xx::read_measurements
+    /// [`finish`]: Si70xx::finish
+    /// [`with_on_success`]: Si70xx::with_on_success
+    pub fn with_after_read<F>(self, after_read: F) -> Driver<I2C, M, Hooks<B, S, BM, F>>
+    where
+        F: FnMut(&Measurement),
+    {
+        self.map_hooks(|hooks| Hooks {
+            bus_clear_check: hooks.bus_clear_check,
+            on_success: hooks.on_success,
+            before_measure: hooks.before_measure,
+            after_read: Some(after_read),
+        })
+    }
+
+    /// Replaces the hooks, keeping all other settings and state of the driver.
+    fn map_hooks<H>(self, f: impl FnOnce(Hooks<B, S, BM, AR>) -> H) -> Driver<I2C, M, H> {
+        let Driver {
+            i2c,
+            addr,
+            last,
+            timing_margin_ms,
+            supply_check,
+            pending,
+            byte_order,
+            resolution,
+            hooks,
+            rounding,
+            vdd_low,
+            crc,
+            temperature_crc,
+            heater,
+            heater_current,
+            auto_reset_after,
+            crc_failures,
+            mode,
+        } = self;
+        Driver {
+            i2c,
+            addr,
+            last,
+            timing_margin_ms,
+            supply_check,
+            pending,
+            byte_order,
+            resolution,
+            hooks: f(hooks),
+            rounding,
+            vdd_low,
+            crc,
+            temperature_crc,
+            heater,
+            heater_current,
+            auto_reset_after,
+            crc_failures,
+            mode,
+        }
+    }
+}
+
+driver_impl!(Blocking);
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use embedded_hal::i2c::NoAcknowledgeSource;
+    use embedded_hal_mock::eh1::delay::{CheckedDelay, Transaction as DelayTransaction};
+    use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+    use std::vec;
+
+    fn new_sensor(i2c: I2cMock) -> Si70xx<I2cMock> {
+        Si70xx::new(i2c)
+    }
+
+    const EXPECTED: u8 = crc8_const(&[0x3A, 0x00]);
+    const _: () = assert!(crc8_const(&[0x66, 0x4E]) == 0x2D);
+
+    #[test]
+    fn crc_valid_worked_examples() {
+        assert_eq!(crc8(&[0xDC]), 0x79);
+        assert!(crc_valid(&[0x68, 0x3A, 0x7C]));
+        assert!(crc_valid(&[0x4E, 0x85, 0x6B]));
+        assert!(!crc_valid(&[0x4E, 0x85, 0x6A]));
+        assert!(!crc_valid(&[0x4F, 0x85, 0x6B]));
+    }
+
+    #[test]
+    fn crc8_matches_const() {
+        assert_eq!(EXPECTED, 0x5C);
+        assert_eq!(crc8(&[0x3A, 0x00]), EXPECTED);
+        assert_eq!(crc8(&[0x66, 0x4E]), crc8_const(&[0x66, 0x4E]));
+        assert_eq!(crc8(&[]), 0x00);
+    }
+
+    #[test]
+    fn temperature_rounding_modes() {
+        use RoundingMode::*;
+        // (code, truncate, nearest, floor, ceil)
+        let cases = [
+            (16384, -292, -292, -292, -292), // -292.00
+            (16385, -291, -292, -292, -291), // -291.73
+            (16386, -291, -291, -292, -291), // -291.46
+            (30000, 3358, 3359, 3358, 3359), //  3358.82
+        ];
+        for (code, truncate, nearest, floor, ceil) in cases {
+            assert_eq!(temperature_from_code(code, Truncate), truncate);
+            assert_eq!(temperature_from_code(code, Nearest), nearest);
+            assert_eq!(temperature_from_code(code, Floor), floor);
+            assert_eq!(temperature_from_code(code, Ceil), ceil);
+        }
+    }
+
+    #[test]
+    fn humidity_rounding_modes() {
+        use RoundingMode::*;
+        // 5479.10 and 5122.05
+        assert_eq!(humidity_from_code(0x7C80, Truncate), 5479);
+        assert_eq!(humidity_from_code(0x7C80, Nearest), 5479);
+        assert_eq!(humidity_from_code(0x7C80, Ceil), 5480);
+        assert_eq!(humidity_from_code(30000, Floor), 5122);
+        assert_eq!(humidity_from_code(30000, Ceil), 5123);
+        // -0.52
+        assert_eq!(humidity_from_code_unclamped(3143, Truncate), 0);
+        assert_eq!(humidity_from_code_unclamped(3143, Nearest), -1);
+        assert_eq!(humidity_from_code_unclamped(3143, Floor), -1);
+        assert_eq!(humidity_from_code_unclamped(3143, Ceil), 0);
+    }
+
+    #[test]
+    fn humidity_accuracy_follows_si7021_curve() {
+        // (reading, accuracy), percentages multiplied by 100
+        let cases = [
+            (0, 300),
+            (5000, 300),
+            (8000, 300),
+            (9000, 400),
+            (10000, 500),
+            (12000, 500),
+        ];
+        for (rh, accuracy) in cases {
+            assert_eq!(humidity_accuracy_centi(rh), accuracy);
+        }
+    }
+
+    #[test]
+    fn temperature_accuracy_follows_si7021_curve() {
+        // (reading, accuracy), Celsius multiplied by 100
+        let cases = [
+            (-5000, 70),
+            (-4000, 70),
+            (-2500, 55),
+            (-1000, 40),
+            (2500, 40),
+            (8500, 40),
+            (10500, 55),
+            (12500, 70),
+            (i16::MAX, 70),
+        ];
+        for (temp, accuracy) in cases {
+            assert_eq!(temperature_accuracy_centi(temp), accuracy);
+        }
+    }
+
+    #[test]
+    fn humidity_range_stays_within_0_to_100_percent() {
+        let range = |humidity| {
+            Measurement {
+                humidity,
+                temperature: 2500,
+            }
+            .humidity_range()
+        };
+        assert_eq!(range(5000), (4700, 5300));
+        assert_eq!(range(100), (0, 400));
+        assert_eq!(range(9000), (8600, 9400));
+        assert_eq!(range(10500), (9500, 10000));
+        assert_eq!(range(u16::MAX), (9500, 10000));
+    }
+
+    #[test]
+    fn humidity_codes_outside_range_are_clamped() {
+        for (code, expected) in [(0x0000, 0), (0x0100, 0), (0xFF00, 10000), (0xFFFC, 10000)] {
+            assert_eq!(humidity_from_code(code, RoundingMode::Nearest), expected);
+        }
+        let conversion = |code: u16| {
+            let [msb, lsb] = code.to_be_bytes();
+            [
+                I2cTransaction::write(0x40, vec![0xF5]),
+                I2cTransaction::read(0x40, vec![msb, lsb, crc8(&[msb, lsb])]),
+                I2cTransaction::write_read(0x40, vec![0xE0], vec![0x66, 0x4C]),
+            ]
+        };
+        let mut expectations = vec![];
+        for code in [0x0100, 0xFF00, 0xFFFC] {
+            expectations.extend(conversion(code));
+        }
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&vec![DelayTransaction::delay_ms(24); 3]);
+        let mut sensor = new_sensor(i2c.clone());
+
+        let dry = sensor.read_measurements(&mut delay).unwrap();
+        assert_eq!(dry.humidity, 0);
+        assert_eq!(dry.humidity().centi(), 0);
+        assert_eq!(dry.humidity_range(), (0, 300));
+        assert!(dry.dew_point() < -4000);
+        for _ in 0..2 {
+            let wet = sensor.read_measurements(&mut delay).unwrap();
+            assert_eq!(wet.humidity, 10000);
+            assert_eq!(wet.humidity().centi(), 10000);
+            assert_eq!(wet.humidity_range(), (9500, 10000));
+            assert!((wet.dew_point() - wet.temperature).abs() <= 1);
+        }
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn settling_reached_compares_spread_with_threshold() {
+        assert!(settling_reached(&[5230, 5250, 5240], 20));
+        assert!(!settling_reached(&[5230, 5251, 5240], 20));
+        assert!(!settling_reached(&[5230], 20));
+        assert!(!settling_reached(&[], 20));
+    }
+
+    #[test]
+    fn read_humidity_saturated_flags_codes_above_100_percent() {
+        let frame = |code: u16| {
+            let bytes = code.to_be_bytes();
+            vec![bytes[0], bytes[1], crc8(&bytes)]
+        };
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xF5]),
+            // 100.0009% before rounding.
+            I2cTransaction::read(0x40, frame(0xD917)),
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, frame(0xD900)),
+            I2cTransaction::write(0x40, vec![0xF5]),
+            // -0.0186% before rounding.
+            I2cTransaction::read(0x40, frame(0x0C40)),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&vec![DelayTransaction::delay_ms(24); 3]);
+        let mut sensor = new_sensor(i2c.clone());
+        assert_eq!(
+            sensor.read_humidity_saturated(&mut delay).unwrap(),
+            (10000, true)
+        );
+        assert_eq!(
+            sensor.read_humidity_saturated(&mut delay).unwrap(),
+            (9996, false)
+        );
+        assert_eq!(
+            sensor.read_humidity_saturated(&mut delay).unwrap(),
+            (0, false)
+        );
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn dew_point_matches_magnus_formula() {
+        let dew_point = |humidity, temperature| {
+            Measurement {
+                humidity,
+                temperature,
+            }
+            .dew_point()
+        };
+        assert!((dew_point(6000, 2500) - 1669).abs() <= 1);
+        assert!((dew_point(10000, 2000) - 2000).abs() <= 1);
+        assert!((dew_point(3000, -1000) - -2433).abs() <= 1);
+        assert!((dew_point(9000, 500) - 350).abs() <= 1);
+
+        let humid = Measurement {
+            humidity: 9000,
+            temperature: 500,
+        };
+        assert!(humid.condensation_risk());
+        assert!(!humid.condensation_risk_within(100));
+        let dry = Measurement {
+            humidity: 6000,
+            temperature: 2500,
+        };
+        assert!(!dry.condensation_risk());
+    }
+
+    #[test]
+    fn humidity_newtype_clamps() {
+        let m = Measurement {
+            humidity: 10040,
+            temperature: -1234,
+        };
+        assert_eq!(m.humidity().centi(), 10000);
+        assert_eq!(m.temperature().centi(), -1234);
+        assert_eq!(Humidity::from_centi(4955).centi(), 4955);
+    }
+
+    #[test]
+    fn absolute_humidity_matches_magnus_formula() {
+        let m = Measurement {
+            humidity: 5000,
+            temperature: 2500,
+        };
+        // 216.7 * 0.5 * 31.60 hPa / 298.15 K
+        assert!((m.absolute_humidity() as i32 - 1148).abs() <= 1);
+        let dry = Measurement {
+            humidity: 0,
+            temperature: 2500,
+        };
+        assert_eq!(dry.absolute_humidity(), 0);
+    }
+
+    #[cfg(feature = "heapless")]
+    #[test]
+    fn measurement_to_string() {
+        let text = |humidity, temperature| {
+            Measurement {
+                humidity,
+                temperature,
+            }
+            .to_string()
+        };
+        assert_eq!(text(5230, 2481), "52.3%RH 24.81C");
+        assert_eq!(text(5236, 2500), "52.4%RH 25.00C");
+        assert_eq!(text(0, -5), "0.0%RH -0.05C");
+        assert_eq!(text(9999, -1234), "100.0%RH -12.34C");
+        assert_eq!(text(u16::MAX, i16::MIN), "655.4%RH -327.68C");
+        assert_eq!(text(u16::MAX, i16::MAX), "655.4%RH 327.67C");
+    }
+
+    #[test]
+    fn measurement_delta() {
+        let a = Measurement {
+            humidity: 5230,
+            temperature: 150,
+        };
+        let b = Measurement {
+            humidity: 4980,
+            temperature: -210,
+        };
+        assert_eq!(a.delta(&b), (250, 360));
+        assert_eq!(b.delta(&a), (-250, -360));
+        assert_eq!(a.delta(&a), (0, 0));
+    }
+
+    #[test]
+    fn measurement_delta_saturates() {
+        let a = Measurement {
+            humidity: u16::MAX,
+            temperature: i16::MAX,
+        };
+        let b = Measurement {
+            humidity: 0,
+            temperature: i16::MIN,
+        };
+        assert_eq!(a.delta(&b), (i16::MAX, i16::MAX));
+        assert_eq!(b.delta(&a), (i16::MIN, i16::MIN));
+    }
+
+    #[test]
+    fn thermistor_coefficients_are_written_in_sequence() {
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xC5, 0x82, 0x01]),
+            I2cTransaction::write(0x40, vec![0xC5, 0x83, 0x02]),
+            I2cTransaction::write_read(0x40, vec![0x84, 0x83], vec![0x02]),
+            I2cTransaction::write(0x40, vec![0xEE]),
+            I2cTransaction::read(0x40, vec![0x12, 0x34]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut sensor = new_sensor(i2c.clone());
+        sensor
+            .write_thermistor_coefficients(0x82, &[0x01, 0x02])
+            .unwrap();
+        assert_eq!(sensor.read_thermistor_coefficient(0x83).unwrap(), 0x02);
+        sensor.measure_aux().unwrap();
+        assert_eq!(sensor.read_aux().unwrap(), 0x1234);
+        i2c.done();
+    }
+
+    #[test]
+    fn thermistor_waits_its_own_conversion_time() {
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xEE]),
+            I2cTransaction::read(0x40, vec![0x12, 0x34]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[DelayTransaction::delay_ms(
+            THERMISTOR_CONVERSION_TIME_MS + DEFAULT_TIMING_MARGIN_MS,
+        )]);
+        let mut sensor = new_sensor(i2c.clone());
+        assert_eq!(sensor.read_thermistor(&mut delay).unwrap(), 0x1234);
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn thermistor_compensation_matches_hand_computed_value() {
+        // -10.00 + 0.5 per code step + 0.25 per degree of die temperature above 25.00.
+        let coeffs = ThermistorCoefficients {
+            offset: -1000,
+            gain: 32768,
+            die_gain: 16384,
+        };
+        // -10.00 + 0.5 * 4660 + 0.25 * 4.00 = 14.30
+        assert_eq!(coeffs.apply(4660, 2900), 1430);
+        assert_eq!(coeffs.apply(u16::MAX, i16::MAX), i16::MAX);
+
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xEE]),
+            I2cTransaction::read(0x40, vec![0x12, 0x34]),
+            I2cTransaction::write(0x40, vec![0xF3]),
+            // 0x6E81 converts to 29.00.
+            I2cTransaction::read(0x40, vec![0x6E, 0x81, crc8(&[0x6E, 0x81])]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[
+            DelayTransaction::delay_ms(THERMISTOR_CONVERSION_TIME_MS + DEFAULT_TIMING_MARGIN_MS),
+            DelayTransaction::delay_ms(12),
+        ]);
+        let mut sensor = new_sensor(i2c.clone());
+        assert_eq!(
+            sensor
+                .read_thermistor_compensated(&mut delay, &coeffs)
+                .unwrap(),
+            1430
+        );
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn reserved_bits_survive_set_resolution() {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]),
+            I2cTransaction::write(0x40, vec![0xE6, 0xBB]),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0xBB]),
+            I2cTransaction::write(0x40, vec![0xE6, 0x3A]),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut sensor = new_sensor(i2c.clone());
+        assert_eq!(sensor.read_user_register_reserved().unwrap(), 0x3A);
+        sensor.set_resolution(Resolution::Rh11Temp11).unwrap();
+        sensor.set_resolution(Resolution::Rh12Temp14).unwrap();
+        assert_eq!(sensor.read_user_register_reserved().unwrap(), 0x3A);
+        i2c.done();
+    }
+
+    #[test]
+    fn vdd_low_latch_stays_set() {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x7A]),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut sensor = new_sensor(i2c.clone());
+        assert!(!sensor.vdd_low_since_reset());
+        sensor.read_user_register_reserved().unwrap();
+        sensor.read_user_register_reserved().unwrap();
+        assert!(sensor.vdd_low_since_reset());
+        sensor.clear_vdd_latch();
+        assert!(!sensor.vdd_low_since_reset());
+        i2c.done();
+    }
+
+    #[test]
+    fn crc_error_names_frame() {
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80, crc8(&[0x7C, 0x80])]),
+            I2cTransaction::write(0x40, vec![0xF3]),
+            I2cTransaction::read(0x40, vec![0x66, 0x4C, crc8(&[0x66, 0x4C]) ^ 0x01]),
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x81, crc8(&[0x7C, 0x80])]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[
+            DelayTransaction::delay_ms(24),
+            DelayTransaction::delay_ms(12),
+            DelayTransaction::delay_ms(24),
+        ]);
+        let mut sensor = new_sensor(i2c.clone()).with_temperature_crc(true);
+        assert!(matches!(
+            sensor.read_measurements(&mut delay),
+            Err(Error::Crc(Frame::Temperature))
+        ));
+        assert!(matches!(
+            sensor.read_measurements(&mut delay),
+            Err(Error::Crc(Frame::Humidity))
+        ));
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn supply_check_skips_conversion_on_low_supply() {
+        let expectations = [I2cTransaction::write_read(0x40, vec![0xE7], vec![0x7A])];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[]);
+        let mut sensor = new_sensor(i2c.clone()).with_supply_check(true);
+        assert!(matches!(
+            sensor.read_measurements(&mut delay),
+            Err(Error::LowSupply)
+        ));
+        assert_eq!(sensor.pending_command(), None);
+        assert!(sensor.vdd_low_since_reset());
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn rail_codes_are_invalid_data() {
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0xFF, 0xFF]),
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80]),
+            I2cTransaction::write_read(0x40, vec![0xE0], vec![0x00, 0x00]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[
+            DelayTransaction::delay_ms(24),
+            DelayTransaction::delay_ms(24),
+        ]);
+        let mut sensor = new_sensor(i2c.clone()).with_crc(false);
+        assert!(matches!(
+            sensor.read_measurements(&mut delay),
+            Err(Error::InvalidData)
+        ));
+        assert!(matches!(
+            sensor.read_measurements(&mut delay),
+            Err(Error::InvalidData)
+        ));
+        assert_eq!(sensor.last_humidity(), None);
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn byte_order_decodes_swapped_codes() {
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80, 0xF5]),
+            I2cTransaction::write_read(0x40, vec![0xE0], vec![0x66, 0x4C]),
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0x80, 0x7C, crc8(&[0x80, 0x7C])]),
+            I2cTransaction::write_read(0x40, vec![0xE0], vec![0x4C, 0x66]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[
+            DelayTransaction::delay_ms(24),
+            DelayTransaction::delay_ms(24),
+        ]);
+        let expected = Measurement {
+            humidity: humidity_from_code(0x7C80, RoundingMode::Nearest),
+            temperature: temperature_from_code(0x664C, RoundingMode::Nearest),
+        };
+        let mut sensor = new_sensor(i2c.clone()).with_byte_order(ByteOrder::BigEndian);
+        assert_eq!(sensor.read_measurements(&mut delay).unwrap(), expected);
+        let mut sensor = sensor.with_byte_order(ByteOrder::LittleEndian);
+        assert_eq!(sensor.read_measurements(&mut delay).unwrap(), expected);
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn read_raw_frames_returns_bytes_unchanged() {
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80, 0xF5]),
+            I2cTransaction::write(0x40, vec![0xF3]),
+            I2cTransaction::read(0x40, vec![0x66, 0x4C, crc8(&[0x66, 0x4C]) ^ 0x01]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[
+            DelayTransaction::delay_ms(24),
+            DelayTransaction::delay_ms(12),
+        ]);
+        // Neither byte order nor checksum validation apply to raw frames.
+        let mut sensor = new_sensor(i2c.clone()).with_byte_order(ByteOrder::LittleEndian);
+        assert_eq!(
+            sensor.read_raw_frames(&mut delay).unwrap(),
+            ([0x7C, 0x80, 0xF5], [0x66, 0x4C, crc8(&[0x66, 0x4C]) ^ 0x01])
+        );
+        assert_eq!(sensor.pending_command(), None);
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn health_ping_checks_temperature_range() {
+        let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xF3]),
+            I2cTransaction::read(0x40, vec![0x66, 0x4C, crc8(&[0x66, 0x4C])]),
+            I2cTransaction::write(0x40, vec![0xF3]),
+            // 128.86°C, above the operating range.
+            I2cTransaction::read(0x40, vec![0xFF, 0xFC, crc8(&[0xFF, 0xFC])]),
+            I2cTransaction::write(0x40, vec![0xF3]).with_error(nack),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[
+            DelayTransaction::delay_ms(12),
+            DelayTransaction::delay_ms(12),
+        ]);
+        let mut sensor = new_sensor(i2c.clone());
+        assert!(sensor.health_ping(&mut delay).unwrap());
+        assert!(!sensor.health_ping(&mut delay).unwrap());
+        assert!(matches!(
+            sensor.health_ping(&mut delay),
+            Err(Error::I2c(ErrorKind::NoAcknowledge(_)))
+        ));
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn crc_disabled_reads_two_bytes() {
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80]),
+            I2cTransaction::write_read(0x40, vec![0xE0], vec![0x66, 0x4C]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[DelayTransaction::delay_ms(24)]);
+        let mut sensor = new_sensor(i2c.clone())
+            .with_crc(false)
+            .with_temperature_crc(true);
+        let m = sensor.read_measurements(&mut delay).unwrap();
+        assert_eq!(
+            m.humidity,
+            humidity_from_code(0x7C80, RoundingMode::Nearest)
+        );
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn measure_no_hold_retries_nack() {
+        let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0, 0, 0]).with_error(nack),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80, 0xF5]),
+            I2cTransaction::write_read(0x40, vec![0xE0], vec![0x66, 0x4C]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[
+            DelayTransaction::delay_ms(24),
+            DelayTransaction::delay_ms(1),
+        ]);
+        let mut sensor = new_sensor(i2c.clone());
+        let m = sensor.measure_no_hold(&mut delay).unwrap();
+        assert_eq!(
+            m.humidity,
+            humidity_from_code(0x7C80, RoundingMode::Nearest)
+        );
+        assert_eq!(sensor.pending_command(), None);
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn read_measurements_into_uses_caller_buffer() {
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80, 0xF5]),
+            I2cTransaction::write_read(0x40, vec![0xE0], vec![0x66, 0x4C]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[DelayTransaction::delay_ms(24)]);
+        let mut sensor = new_sensor(i2c.clone());
+        let mut short = [0u8; MIN_BUFFER_LEN - 1];
+        assert!(matches!(
+            sensor.read_measurements_into(&mut delay, &mut short),
+            Err(Error::BufferTooSmall)
+        ));
+        let mut buffer = [0xAAu8; 4];
+        let m = sensor
+            .read_measurements_into(&mut delay, &mut buffer)
+            .unwrap();
+        assert_eq!(
+            m,
+            Measurement {
+                humidity: humidity_from_code(0x7C80, RoundingMode::Nearest),
+                temperature: temperature_from_code(0x664C, RoundingMode::Nearest),
+            }
+        );
+        // Temperature response overwrote the humidity frame, the rest is untouched.
+        assert_eq!(buffer, [0x66, 0x4C, 0xF5, 0xAA]);
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn read_measurements_into_validates_temperature_crc() {
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80, 0xF5]),
+            I2cTransaction::write(0x40, vec![0xF3]),
+            I2cTransaction::read(0x40, vec![0x66, 0x4C, crc8(&[0x66, 0x4C])]),
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80, 0xF5]),
+            I2cTransaction::write(0x40, vec![0xF3]),
+            I2cTransaction::read(0x40, vec![0x66, 0x4C, crc8(&[0x66, 0x4C]) ^ 0x01]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[
+            DelayTransaction::delay_ms(24),
+            DelayTransaction::delay_ms(12),
+            DelayTransaction::delay_ms(24),
+            DelayTransaction::delay_ms(12),
+        ]);
+        let mut sensor = new_sensor(i2c.clone()).with_temperature_crc(true);
+        let mut buffer = [0u8; MIN_BUFFER_LEN];
+        let m = sensor
+            .read_measurements_into(&mut delay, &mut buffer)
+            .unwrap();
+        assert_eq!(
+            m.temperature,
+            temperature_from_code(0x664C, RoundingMode::Nearest)
+        );
+        assert!(matches!(
+            sensor.read_measurements_into(&mut delay, &mut buffer),
+            Err(Error::Crc(Frame::Temperature))
+        ));
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn backoff_doubles_wait_between_retries() {
+        let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xF5]).with_error(nack),
+            I2cTransaction::write(0x40, vec![0xF5]).with_error(ErrorKind::ArbitrationLoss),
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80, 0xF5]),
+            I2cTransaction::write_read(0x40, vec![0xE0], vec![0x66, 0x4C]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[
+            DelayTransaction::delay_ms(5),
+            DelayTransaction::delay_ms(10),
+            DelayTransaction::delay_ms(24),
+        ]);
+        let mut sensor = new_sensor(i2c.clone());
+        sensor.read_measurements_backoff(&mut delay, 2, 5).unwrap();
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn backoff_stops_at_retry_limit() {
+        let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xF5]).with_error(nack),
+            I2cTransaction::write(0x40, vec![0xF5]).with_error(nack),
+            // Checksum errors are not retried.
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80, 0xF4]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[
+            DelayTransaction::delay_ms(5),
+            DelayTransaction::delay_ms(24),
+        ]);
+        let mut sensor = new_sensor(i2c.clone());
+        assert!(matches!(
+            sensor.read_measurements_backoff(&mut delay, 1, 5),
+            Err(Error::I2c(_))
+        ));
+        assert!(matches!(
+            sensor.read_measurements_backoff(&mut delay, 3, 5),
+            Err(Error::Crc(Frame::Humidity))
+        ));
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn read_ready_returns_none_until_acknowledged() {
+        let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0, 0, 0]).with_error(nack),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80, 0xF5]),
+            I2cTransaction::write_read(0x40, vec![0xE0], vec![0x66, 0x4C]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut sensor = new_sensor(i2c.clone());
+        assert_eq!(sensor.read_ready().unwrap(), None);
+        assert_eq!(sensor.read_ready().unwrap(), None);
+        let m = sensor.read_ready().unwrap().unwrap();
+        assert_eq!(
+            m.humidity,
+            humidity_from_code(0x7C80, RoundingMode::Nearest)
+        );
+        assert_eq!(sensor.pending_command(), None);
+        i2c.done();
+    }
+
+    #[test]
+    fn finish_checks_deadline_before_reading() {
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80, 0xF5]),
+            I2cTransaction::write_read(0x40, vec![0xE0], vec![0x66, 0x4C]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let now = core::cell::Cell::new(100);
+        let mut clock = || now.get();
+        let mut sensor = new_sensor(i2c.clone());
+
+        let handle = sensor.start(&mut clock).unwrap();
+        assert_eq!(handle.ready_at_ms(), 124);
+        assert_eq!(
+            sensor.pending_command(),
+            Some(MeasurementMode::NoHoldMaster)
+        );
+        now.set(123);
+        assert!(!handle.is_ready(&mut clock));
+        assert!(matches!(
+            sensor.finish(&handle, &mut clock),
+            Err(Error::NotReady)
+        ));
+
+        now.set(handle.ready_at_ms());
+        let m = sensor.finish(&handle, &mut clock).unwrap();
+        assert_eq!(
+            m.temperature,
+            temperature_from_code(0x664C, RoundingMode::Nearest)
+        );
+        assert_eq!(sensor.last_humidity(), Some(m.humidity));
+        assert_eq!(sensor.pending_command(), None);
+        i2c.done();
+    }
+
+    #[test]
+    fn benchmark_conversion_measures_time_until_acknowledged() {
+        let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0, 0]).with_error(nack),
+            I2cTransaction::read(0x40, vec![0, 0]).with_error(nack),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80]),
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0, 0]).with_error(nack),
+            I2cTransaction::read(0x40, vec![0, 0]).with_error(nack),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let step = core::cell::Cell::new(6);
+        let now = core::cell::Cell::new(0);
+        let mut clock = || {
+            now.set(now.get() + step.get());
+            now.get()
+        };
+        let mut sensor = new_sensor(i2c.clone());
+        assert_eq!(sensor.benchmark_conversion(&mut clock).unwrap(), 18);
+        assert_eq!(sensor.pending_command(), None);
+        step.set(60);
+        assert!(matches!(
+            sensor.benchmark_conversion(&mut clock),
+            Err(Error::NotReady)
+        ));
+        i2c.done();
+    }
+
+    #[test]
+    fn characterize_sweeps_all_resolutions() {
+        let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+        let resolutions = [
+            (Resolution::Rh12Temp14, 0x00, 2),
+            (Resolution::Rh8Temp12, 0x01, 0),
+            (Resolution::Rh10Temp13, 0x80, 1),
+            (Resolution::Rh11Temp11, 0x81, 0),
+        ];
+        let mut expectations = vec![];
+        let mut delays = vec![];
+        for (i, (_, bits, nacks)) in resolutions.iter().enumerate() {
+            let rh = [0x7C, 0x80 + 4 * i as u8];
+            expectations.push(I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]));
+            expectations.push(I2cTransaction::write(0x40, vec![0xE6, 0x3A | bits]));
+            expectations.push(I2cTransaction::write(0x40, vec![0xF5]));
+            for _ in 0..*nacks {
+                expectations.push(I2cTransaction::read(0x40, vec![0, 0, 0]).with_error(nack));
+                delays.push(DelayTransaction::delay_ms(1));
+            }
+            expectations.push(I2cTransaction::read(0x40, vec![rh[0], rh[1], crc8(&rh)]));
+            expectations.push(I2cTransaction::write_read(
+                0x40,
+                vec![0xE0],
+                vec![0x66, 0x4C + i as u8],
+            ));
+        }
+        // Original resolution is restored.
+        expectations.push(I2cTransaction::write_read(0x40, vec![0xE7], vec![0xBB]));
+        expectations.push(I2cTransaction::write(0x40, vec![0xE6, 0x3A]));
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&delays);
+        let mut sensor = new_sensor(i2c.clone());
+
+        let characterization = sensor.characterize(&mut delay).unwrap();
+        for (i, (resolution, _, nacks)) in resolutions.iter().enumerate() {
+            assert_eq!(
+                characterization.profiles[i],
+                ResolutionProfile {
+                    resolution: *resolution,
+                    rh_code: 0x7C80 + 4 * i as u16,
+                    temperature_code: 0x664C + i as u16,
+                    conversion_time_ms: *nacks,
+                }
+            );
+        }
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn characterize_returns_sweep_error_when_restore_fails() {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]),
+            I2cTransaction::write(0x40, vec![0xE6, 0x3A]),
+            I2cTransaction::write(0x40, vec![0xF5]).with_error(ErrorKind::Bus),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]).with_error(ErrorKind::Other),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[]);
+        let mut sensor = new_sensor(i2c.clone());
+        assert!(matches!(
+            sensor.characterize(&mut delay),
+            Err(Error::I2c(ErrorKind::Bus))
+        ));
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn hooks_can_capture_state() {
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80, 0xF5]),
+            I2cTransaction::write_read(0x40, vec![0xE0], vec![0x66, 0x4C]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[DelayTransaction::delay_ms(24)]);
+        let bus_idle = core::cell::Cell::new(false);
+        let started = core::cell::Cell::new(0);
+        let read = core::cell::Cell::new(None);
+        let petted = core::cell::Cell::new(0);
+        let mut sensor = new_sensor(i2c.clone())
+            .with_bus_clear_check(|| bus_idle.get())
+            .with_before_measure(|| started.set(started.get() + 1))
+            .with_after_read(|m| read.set(Some(*m)))
+            .with_on_success(|| petted.set(petted.get() + 1));
+
+        assert!(matches!(
+            sensor.read_measurements(&mut delay),
+            Err(Error::BusStuck)
+        ));
+        assert_eq!(started.get(), 0);
+        bus_idle.set(true);
+        let m = sensor.read_measurements(&mut delay).unwrap();
+        assert_eq!(started.get(), 1);
+        assert_eq!(read.get(), Some(m));
+        assert_eq!(petted.get(), 1);
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn heater_keeps_other_bits() {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0xBB]),
+            I2cTransaction::write(0x40, vec![0xE6, 0xBF]),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0xBF]),
+            I2cTransaction::write_read(0x40, vec![0x11], vec![0xA0]),
+            I2cTransaction::write(0x40, vec![0x51, 0xA8]),
+            I2cTransaction::write_read(0x40, vec![0x11], vec![0xA8]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut sensor = new_sensor(i2c.clone());
+        sensor.set_heater_enabled(true).unwrap();
+        assert!(sensor.heater_enabled().unwrap());
+        sensor.set_heater_current(HeaterCurrent::Level8).unwrap();
+        assert_eq!(sensor.heater_current().unwrap(), HeaterCurrent::Level8);
+        i2c.done();
+    }
+
+    #[test]
+    fn enable_and_disable_heater() {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0x11], vec![0x00]),
+            I2cTransaction::write(0x40, vec![0x51, 0x04]),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]),
+            I2cTransaction::write(0x40, vec![0xE6, 0x3E]),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3E]),
+            I2cTransaction::write_read(0x40, vec![0x11], vec![0x04]),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3E]),
+            I2cTransaction::write(0x40, vec![0xE6, 0x3A]),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut sensor = new_sensor(i2c.clone());
+        sensor.enable_heater(HeaterCurrent::Level4).unwrap();
+        assert_eq!(sensor.heater_level().unwrap(), Some(HeaterCurrent::Level4));
+        sensor.disable_heater().unwrap();
+        assert_eq!(sensor.heater_level().unwrap(), None);
+        i2c.done();
+    }
+
+    #[test]
+    fn read_device_from_serial_number() {
+        let sna = [0x12, 0x34, 0x56, 0x78];
+        let snb = [0x15, 0xFF, 0xAB, 0xCD];
+        let first = sna.iter().flat_map(|b| [*b, crc8(&[*b])]).collect();
+        let second = vec![
+            snb[0],
+            snb[1],
+            crc8(&snb[..2]),
+            snb[2],
+            snb[3],
+            crc8(&snb[2..]),
+        ];
+        let mut corrupted: std::vec::Vec<u8> = second.clone();
+        corrupted[5] ^= 0x01;
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0xFA, 0x0F], first),
+            I2cTransaction::write_read(0x40, vec![0xFC, 0xC9], second),
+            I2cTransaction::write_read(0x40, vec![0xFA, 0x0F], vec![0; 8]),
+            I2cTransaction::write_read(0x40, vec![0xFC, 0xC9], corrupted),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut sensor = new_sensor(i2c.clone());
+        assert_eq!(sensor.read_device().unwrap(), Device::Si7021);
+        assert!(matches!(
+            sensor.read_serial_number(),
+            Err(Error::Crc(Frame::SerialNumber))
+        ));
+        i2c.done();
+    }
+
+    #[test]
+    fn new_configured_uses_address_from_config() {
+        let expectations = [
+            I2cTransaction::write_read(0x41, vec![0xE7], vec![0x3A]),
+            I2cTransaction::write(0x41, vec![0xE6, 0x3B]),
+            I2cTransaction::write_read(0x41, vec![0xE7], vec![0x3B]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[DelayTransaction::delay_ms(POWER_UP_TIME_MS)]);
+        let config = DeviceConfig {
+            address: Address::H41,
+            resolution: Resolution::Rh8Temp12,
+            heater: false,
+        };
+        Si70xx::new_configured(i2c.clone(), &mut delay, config).unwrap();
+        delay.done();
+        i2c.done();
+    }
+
+    #[cfg(feature = "heapless")]
+    #[test]
+    fn scan_reports_responding_addresses() {
+        let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]).with_error(nack),
+            I2cTransaction::write_read(0x41, vec![0xE7], vec![0x3A]),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]).with_error(nack),
+            I2cTransaction::write_read(0x41, vec![0xE7], vec![0x3A]).with_error(nack),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]).with_error(ErrorKind::Bus),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        assert_eq!(Si70xx::scan(&mut i2c).unwrap().as_slice(), &[0x41]);
+        assert!(Si70xx::scan(&mut i2c).unwrap().is_empty());
+        assert!(matches!(
+            Si70xx::scan(&mut i2c),
+            Err(Error::I2c(ErrorKind::Bus))
+        ));
+        i2c.done();
+    }
+
+    #[cfg(feature = "heapless")]
+    #[test]
+    fn scan_range_skips_reserved_addresses() {
+        let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+        let id = vec![
+            0x15,
+            0xFF,
+            crc8(&[0x15, 0xFF]),
+            0xAB,
+            0xCD,
+            crc8(&[0xAB, 0xCD]),
+        ];
+        let expectations: std::vec::Vec<_> = (0x08..=0x77)
+            .map(|addr| {
+                let transaction = I2cTransaction::write_read(addr, vec![0xFC, 0xC9], id.clone());
+                if addr == 0x40 {
+                    transaction
+                } else {
+                    transaction.with_error(nack)
+                }
+            })
+            .collect();
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[
+            DelayTransaction::delay_ms(POWER_UP_TIME_MS),
+            DelayTransaction::delay_ms(POWER_UP_TIME_MS),
+        ]);
+        let found = Si70xx::scan_range(&mut i2c, &mut delay, 0x00, 0xFF).unwrap();
+        assert_eq!(found.as_slice(), &[(0x40, Device::Si7021)]);
+        assert!(Si70xx::scan_range(&mut i2c, &mut delay, 0x78, 0x7F)
+            .unwrap()
+            .is_empty());
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn recover_applies_driver_settings_after_reset() {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]),
+            I2cTransaction::write(0x40, vec![0xE6, 0xBA]),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0xBA]),
+            I2cTransaction::write(0x40, vec![0xE6, 0xBE]),
+            I2cTransaction::write(0x40, vec![0xFE]),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]),
+            I2cTransaction::write(0x40, vec![0xE6, 0xBE]),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0xBE]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[DelayTransaction::delay_ms(RESET_TIME_MS)]);
+        let mut sensor = new_sensor(i2c.clone());
+        sensor.set_resolution(Resolution::Rh10Temp13).unwrap();
+        sensor.set_heater_enabled(true).unwrap();
+        sensor.recover(&mut delay).unwrap();
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn recover_restores_heater_current() {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0x11], vec![0x00]),
+            I2cTransaction::write(0x40, vec![0x51, 0x09]),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]),
+            I2cTransaction::write(0x40, vec![0xE6, 0x3E]),
+            I2cTransaction::write(0x40, vec![0xFE]),
+            I2cTransaction::write_read(0x40, vec![0x11], vec![0x00]),
+            I2cTransaction::write(0x40, vec![0x51, 0x09]),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]),
+            I2cTransaction::write(0x40, vec![0xE6, 0x3E]),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3E]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[DelayTransaction::delay_ms(RESET_TIME_MS)]);
+        let mut sensor = new_sensor(i2c.clone());
+        sensor.enable_heater(HeaterCurrent::Level9).unwrap();
+        sensor.recover(&mut delay).unwrap();
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn reset_restores_power_on_resolution() {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]),
+            I2cTransaction::write(0x40, vec![0xE6, 0x3B]),
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80, 0xF5]),
+            I2cTransaction::write_read(0x40, vec![0xE0], vec![0x66, 0x4C]),
+            I2cTransaction::write(0x40, vec![0xFE]),
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80, 0xF5]),
+            I2cTransaction::write_read(0x40, vec![0xE0], vec![0x66, 0x4C]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[
+            DelayTransaction::delay_ms(8),
+            DelayTransaction::delay_ms(RESET_TIME_MS),
+            DelayTransaction::delay_ms(24),
+        ]);
+        let mut sensor = new_sensor(i2c.clone());
+        sensor.set_resolution(Resolution::Rh8Temp12).unwrap();
+        sensor.read_measurements(&mut delay).unwrap();
+        sensor.reset(&mut delay).unwrap();
+        // Full 12/14-bit conversion time after the reset.
+        sensor.read_measurements(&mut delay).unwrap();
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn auto_reset_after_consecutive_crc_failures() {
+        let good = || {
+            [
+                I2cTransaction::write(0x40, vec![0xF5]),
+                I2cTransaction::read(0x40, vec![0x7C, 0x80, 0xF5]),
+                I2cTransaction::write_read(0x40, vec![0xE0], vec![0x66, 0x4C]),
+            ]
+        };
+        let bad = || {
+            [
+                I2cTransaction::write(0x40, vec![0xF5]),
+                I2cTransaction::read(0x40, vec![0x7C, 0x80, 0xF4]),
+            ]
+        };
+        let mut expectations = vec![];
+        expectations.extend(bad());
+        expectations.extend(bad());
+        expectations.extend([
+            I2cTransaction::write(0x40, vec![0xFE]),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]),
+            I2cTransaction::write(0x40, vec![0xE6, 0x3A]),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]),
+        ]);
+        expectations.extend(good());
+        // A valid frame in between clears the counter, so no further reset.
+        expectations.extend(bad());
+        expectations.extend(good());
+        expectations.extend(bad());
+        expectations.extend(good());
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delays = vec![DelayTransaction::delay_ms(24); 2];
+        delays.push(DelayTransaction::delay_ms(RESET_TIME_MS));
+        delays.extend(vec![DelayTransaction::delay_ms(24); 5]);
+        let mut delay = CheckedDelay::new(&delays);
+        let mut sensor = new_sensor(i2c.clone()).with_auto_reset_after(Some(2));
+        for _ in 0..2 {
+            assert!(matches!(
+                sensor.read_measurements(&mut delay),
+                Err(Error::Crc(Frame::Humidity))
+            ));
+        }
+        sensor.read_measurements(&mut delay).unwrap();
+        for _ in 0..2 {
+            assert!(sensor.read_measurements(&mut delay).is_err());
+            sensor.read_measurements(&mut delay).unwrap();
+        }
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn temperature_only_skips_humidity_conversion() {
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xF3]),
+            I2cTransaction::read(0x40, vec![0x66, 0x4C, crc8(&[0x66, 0x4C])]),
+            I2cTransaction::write(0x40, vec![0xE3]),
+            I2cTransaction::read(0x40, vec![0x66, 0x4C, crc8(&[0x66, 0x4C])]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[DelayTransaction::delay_ms(12)]);
+        let mut sensor = new_sensor(i2c.clone());
+        let expected = temperature_from_code(0x664C, RoundingMode::Nearest);
+        assert_eq!(sensor.read_temperature_only(&mut delay).unwrap(), expected);
+        assert_eq!(sensor.read_temperature_hold().unwrap(), expected);
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn vdd_status_decodes_vdds_bit() {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x7A]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut sensor = new_sensor(i2c.clone());
+        assert_eq!(sensor.vdd_status().unwrap(), VddStatus::Ok);
+        assert_eq!(sensor.vdd_status().unwrap(), VddStatus::Low);
+        assert!(sensor.vdd_low_since_reset());
+        i2c.done();
+    }
+
+    #[test]
+    fn identify_reads_serial_number_and_firmware() {
+        let sna = [0x12, 0x34, 0x56, 0x78];
+        let snb = [0x14, 0xFF, 0xAB, 0xCD];
+        let first = sna.iter().flat_map(|b| [*b, crc8(&[*b])]).collect();
+        let second = vec![
+            snb[0],
+            snb[1],
+            crc8(&snb[..2]),
+            snb[2],
+            snb[3],
+            crc8(&snb[2..]),
+        ];
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0xFA, 0x0F], first),
+            I2cTransaction::write_read(0x40, vec![0xFC, 0xC9], second),
+            I2cTransaction::write_read(0x40, vec![0x84, 0xB8], vec![0x20]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut sensor = new_sensor(i2c.clone());
+        assert_eq!(
+            sensor.identify().unwrap(),
+            DeviceInfo {
+                serial_number: [0x12, 0x34, 0x56, 0x78, 0x14, 0xFF, 0xAB, 0xCD],
+                device: Device::Si7020,
+                firmware_revision: FirmwareRevision::V2_0,
+            }
+        );
+        i2c.done();
+    }
+
+    #[test]
+    fn resolution_bit_packing() {
+        let cases = [
+            (Resolution::Rh12Temp14, 0x00),
+            (Resolution::Rh8Temp12, 0x01),
+            (Resolution::Rh10Temp13, 0x80),
+            (Resolution::Rh11Temp11, 0x81),
+        ];
+        for (resolution, bits) in cases {
+            assert_eq!(resolution.bits(), bits);
+            assert_eq!(Resolution::from_bits(bits), resolution);
+            // Heater, VDDS and reserved bits do not affect decoding.
+            assert_eq!(Resolution::from_bits(bits | 0x7E), resolution);
+        }
+    }
+
+    #[test]
+    fn resolution_reads_register() {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0xFF]),
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80, 0xF5]),
+            I2cTransaction::write_read(0x40, vec![0xE0], vec![0x66, 0x4C]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[DelayTransaction::delay_ms(11)]);
+        let mut sensor = new_sensor(i2c.clone());
+        assert_eq!(sensor.resolution().unwrap(), Resolution::Rh11Temp11);
+        sensor.read_measurements(&mut delay).unwrap();
+        delay.done();
+        i2c.done();
+    }
+
+    /// Checks that read_measurements waits the datasheet maximum conversion time
+    /// of `resolution` plus default margin before reading out the result.
+    fn assert_measurement_delay(resolution: Resolution, res_bits: u8, expected_ms: u32) {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]),
+            I2cTransaction::write(0x40, vec![0xE6, 0x3A | res_bits]),
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![0x7C, 0x80, 0xF5]),
+            I2cTransaction::write_read(0x40, vec![0xE0], vec![0x66, 0x4C]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[DelayTransaction::delay_ms(expected_ms + 1)]);
+        let mut sensor = new_sensor(i2c.clone());
+        sensor.set_resolution(resolution).unwrap();
+        sensor.read_measurements(&mut delay).unwrap();
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn measurement_delay_rh12_temp14() {
+        // 12 ms + 10.8 ms
+        assert_measurement_delay(Resolution::Rh12Temp14, 0x00, 23);
+    }
+
+    #[test]
+    fn measurement_delay_rh8_temp12() {
+        // 3.1 ms + 3.8 ms
+        assert_measurement_delay(Resolution::Rh8Temp12, 0x01, 7);
+    }
+
+    #[test]
+    fn measurement_delay_rh10_temp13() {
+        // 4.5 ms + 6.2 ms
+        assert_measurement_delay(Resolution::Rh10Temp13, 0x80, 11);
+    }
+
+    #[test]
+    fn measurement_delay_rh11_temp11() {
+        // 7 ms + 2.4 ms
+        assert_measurement_delay(Resolution::Rh11Temp11, 0x81, 10);
     }
 }
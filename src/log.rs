@@ -0,0 +1,227 @@
+//! Compact delta-encoded log of measurements.
+//!
+//! First sample and every sample that changed too much since the previous one is stored
+//! in the 4-byte base format: relative humidity `u16` followed by temperature `i16`, both
+//! big-endian. Other samples are stored as two `i8` differences from the previous sample,
+//! relative humidity first. Relative humidity difference `-128` never appears in a
+//! 2-byte sample, it is written in front of a base format sample instead.
+
+use crate::Measurement;
+
+/// Marks that a sample in the base format follows.
+const ESCAPE: u8 = 0x80;
+
+/// Length of a sample in the base format including [`ESCAPE`].
+const FULL_SAMPLE_LEN: usize = 5;
+
+/// Length of a delta-encoded sample.
+const DELTA_SAMPLE_LEN: usize = 2;
+
+/// Delta-encodes measurements into a fixed `N` byte buffer.
+///
+/// Relative humidity and temperature usually change by less than 1.27 between samples,
+/// such samples take 2 bytes instead of 4. Use [`LogDecoder`] to get the measurements back.
+#[derive(Debug, Clone)]
+pub struct LogEncoder<const N: usize> {
+    buffer: [u8; N],
+    len: usize,
+    last: Option<Measurement>,
+}
+
+impl<const N: usize> LogEncoder<N> {
+    /// Construct new empty encoder.
+    pub fn new() -> Self {
+        Self {
+            buffer: [0; N],
+            len: 0,
+            last: None,
+        }
+    }
+
+    /// Appends `measurement` to the log.
+    ///
+    /// If the buffer does not have room for it, the log is left unchanged and
+    /// `measurement` is given back as an error.
+    pub fn push(&mut self, measurement: Measurement) -> Result<(), Measurement> {
+        let mut sample = [0u8; FULL_SAMPLE_LEN];
+        let sample = match self.last.and_then(|last| Self::delta(&last, &measurement)) {
+            Some((rh, temp)) => {
+                sample[0] = rh as u8;
+                sample[1] = temp as u8;
+                &sample[..DELTA_SAMPLE_LEN]
+            }
+            None => {
+                sample[0] = ESCAPE;
+                sample[1..3].copy_from_slice(&measurement.humidity.to_be_bytes());
+                sample[3..5].copy_from_slice(&measurement.temperature.to_be_bytes());
+                // First sample is always in the base format, it does not need a marker.
+                if self.last.is_none() {
+                    &sample[1..]
+                } else {
+                    &sample[..]
+                }
+            }
+        };
+        let end = self.len + sample.len();
+        if end > N {
+            return Err(measurement);
+        }
+        self.buffer[self.len..end].copy_from_slice(sample);
+        self.len = end;
+        self.last = Some(measurement);
+        Ok(())
+    }
+
+    /// Returns the encoded log.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer[..self.len]
+    }
+
+    /// Discards all measurements, next one is stored in the base format.
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.last = None;
+    }
+
+    /// Returns differences from `last` to `next` if both fit into a 2-byte sample.
+    fn delta(last: &Measurement, next: &Measurement) -> Option<(i8, i8)> {
+        let rh = i8::try_from(next.humidity as i32 - last.humidity as i32).ok()?;
+        let temp = i8::try_from(next.temperature as i32 - last.temperature as i32).ok()?;
+        if rh as u8 == ESCAPE {
+            return None;
+        }
+        Some((rh, temp))
+    }
+}
+
+impl<const N: usize> Default for LogEncoder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Iterates over measurements in a log written by [`LogEncoder`].
+///
+/// Iteration stops at the end of the log or at a truncated sample.
+#[derive(Debug, Clone)]
+pub struct LogDecoder<'a> {
+    data: &'a [u8],
+    last: Option<Measurement>,
+}
+
+impl<'a> LogDecoder<'a> {
+    /// Construct new decoder over an encoded log.
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, last: None }
+    }
+
+    fn full(&mut self, offset: usize) -> Option<Measurement> {
+        let sample = self.data.get(offset..offset + 4)?;
+        self.data = &self.data[offset + 4..];
+        Some(Measurement {
+            humidity: u16::from_be_bytes([sample[0], sample[1]]),
+            temperature: i16::from_be_bytes([sample[2], sample[3]]),
+        })
+    }
+}
+
+impl Iterator for LogDecoder<'_> {
+    type Item = Measurement;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let measurement = match self.last {
+            None => self.full(0)?,
+            Some(_) if self.data.first() == Some(&ESCAPE) => self.full(1)?,
+            Some(last) => {
+                let sample = self.data.get(..DELTA_SAMPLE_LEN)?;
+                let rh = sample[0] as i8;
+                let temp = sample[1] as i8;
+                self.data = &self.data[DELTA_SAMPLE_LEN..];
+                Measurement {
+                    humidity: last.humidity.wrapping_add_signed(rh as i16),
+                    temperature: last.temperature.wrapping_add(temp as i16),
+                }
+            }
+        };
+        self.last = Some(measurement);
+        Some(measurement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn m(humidity: u16, temperature: i16) -> Measurement {
+        Measurement {
+            humidity,
+            temperature,
+        }
+    }
+
+    fn round_trip<const N: usize>(samples: &[Measurement]) -> LogEncoder<N> {
+        let mut encoder = LogEncoder::<N>::new();
+        for sample in samples {
+            encoder.push(*sample).unwrap();
+        }
+        assert!(LogDecoder::new(encoder.as_bytes()).eq(samples.iter().copied()));
+        encoder
+    }
+
+    #[test]
+    fn small_deltas_take_two_bytes() {
+        let samples = [m(5230, 2481), m(5241, 2479), m(5200, 2400), m(5327, 2527)];
+        let encoder = round_trip::<16>(&samples);
+        assert_eq!(encoder.as_bytes().len(), 4 + 3 * 2);
+    }
+
+    #[test]
+    fn negative_temperature_deltas() {
+        let samples = [
+            m(9000, 120),
+            m(9010, 10),
+            m(9005, -100),
+            m(9000, -228),
+            m(9000, -101),
+        ];
+        round_trip::<16>(&samples);
+    }
+
+    #[test]
+    fn large_deltas_use_base_format() {
+        // -128 relative humidity difference is encoded as a base format sample.
+        let samples = [
+            m(5000, 2000),
+            m(4872, 2000),
+            m(10000, -4000),
+            m(0, 12500),
+            m(1, 12499),
+        ];
+        let encoder = round_trip::<32>(&samples);
+        assert_eq!(encoder.as_bytes().len(), 4 + 3 * 5 + 2);
+    }
+
+    #[test]
+    fn full_buffer_rejects_sample() {
+        let mut encoder = LogEncoder::<6>::new();
+        encoder.push(m(5000, 2000)).unwrap();
+        encoder.push(m(5001, 2001)).unwrap();
+        assert_eq!(encoder.push(m(5002, 2002)), Err(m(5002, 2002)));
+        assert!(LogDecoder::new(encoder.as_bytes()).eq([m(5000, 2000), m(5001, 2001)]));
+
+        encoder.clear();
+        assert!(encoder.as_bytes().is_empty());
+        encoder.push(m(100, -100)).unwrap();
+        assert!(LogDecoder::new(encoder.as_bytes()).eq([m(100, -100)]));
+    }
+
+    #[test]
+    fn truncated_log_stops() {
+        let mut encoder = LogEncoder::<16>::new();
+        encoder.push(m(5000, 2000)).unwrap();
+        encoder.push(m(5001, 2001)).unwrap();
+        let bytes = encoder.as_bytes();
+        assert_eq!(LogDecoder::new(&bytes[..bytes.len() - 1]).count(), 1);
+        assert_eq!(LogDecoder::new(&bytes[..3]).count(), 0);
+    }
+}
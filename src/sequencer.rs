@@ -0,0 +1,205 @@
+//! Sampling temperature at a higher rate than relative humidity.
+
+use embedded_hal::{delay::DelayNs, i2c::I2c};
+
+#[cfg(feature = "async")]
+use crate::AsyncSi70xx;
+use crate::{DriverHooks, Error, Measurement, Si70xx};
+
+/// Reads temperature several times per relative humidity measurement.
+///
+/// Every `temperature_reads_per_humidity` calls to [`read`] form a cycle. The first call
+/// of a cycle does a full [`Si70xx::read_measurements`] where temperature comes for free
+/// from the relative humidity conversion. The remaining calls only do a shorter temperature
+/// conversion and return the relative humidity from the start of the cycle.
+///
+/// Temperature read out with 0xE0 is not used in between, although it needs no conversion.
+/// It returns the temperature measured during the last relative humidity conversion, so every
+/// call of a cycle would report the same value and temperature would not be sampled any
+/// faster than relative humidity.
+///
+/// Each returned measurement is kept as the last reading of the driver and passed to its
+/// hooks, the same way as a measurement returned by [`Si70xx::read_measurements`].
+///
+/// [`read`]: SequencedReader::read
+#[derive(Debug)]
+pub struct SequencedReader {
+    temperature_reads_per_humidity: u16,
+    count: u16,
+    humidity: Option<u16>,
+}
+
+impl SequencedReader {
+    /// Construct new sequencer, `0` is treated the same as `1`, which measures relative humidity every time.
+    pub fn new(temperature_reads_per_humidity: u16) -> Self {
+        Self {
+            temperature_reads_per_humidity: temperature_reads_per_humidity.max(1),
+            count: 0,
+            humidity: None,
+        }
+    }
+
+    /// Measures temperature and, when its turn comes, relative humidity.
+    ///
+    /// After an error the next call starts a new cycle with a relative humidity measurement.
+    pub fn read<I2C, E, D, H>(
+        &mut self,
+        sensor: &mut Si70xx<I2C, H>,
+        delay: &mut D,
+    ) -> Result<Measurement, Error<E>>
+    where
+        I2C: I2c<Error = E>,
+        D: DelayNs,
+        H: DriverHooks,
+    {
+        let measurement = match self.humidity {
+            Some(humidity) if self.count != 0 => {
+                let temperature = sensor
+                    .measure_temperature(delay)
+                    .inspect_err(|_| self.humidity = None)?;
+                sensor.complete(Measurement {
+                    humidity,
+                    temperature,
+                })
+            }
+            _ => {
+                self.count = 0;
+                let measurement = sensor.read_measurements(delay)?;
+                self.humidity = Some(measurement.humidity);
+                measurement
+            }
+        };
+        self.count = (self.count + 1) % self.temperature_reads_per_humidity;
+        Ok(measurement)
+    }
+
+    /// Same as [`read`] for [`AsyncSi70xx`].
+    ///
+    /// [`read`]: SequencedReader::read
+    #[cfg(feature = "async")]
+    pub async fn read_async<I2C, E, D, H>(
+        &mut self,
+        sensor: &mut AsyncSi70xx<I2C, H>,
+        delay: &mut D,
+    ) -> Result<Measurement, Error<E>>
+    where
+        I2C: embedded_hal_async::i2c::I2c<Error = E>,
+        D: embedded_hal_async::delay::DelayNs,
+        H: DriverHooks,
+    {
+        let measurement = match self.humidity {
+            Some(humidity) if self.count != 0 => {
+                let temperature = sensor
+                    .measure_temperature(delay)
+                    .await
+                    .inspect_err(|_| self.humidity = None)?;
+                sensor.complete(Measurement {
+                    humidity,
+                    temperature,
+                })
+            }
+            _ => {
+                self.count = 0;
+                let measurement = sensor.read_measurements(delay).await?;
+                self.humidity = Some(measurement.humidity);
+                measurement
+            }
+        };
+        self.count = (self.count + 1) % self.temperature_reads_per_humidity;
+        Ok(measurement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::{crc8, humidity_from_code, temperature_from_code, RoundingMode};
+    use core::cell::Cell;
+    use embedded_hal_mock::eh1::delay::{CheckedDelay, Transaction as DelayTransaction};
+    use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+    use std::vec;
+    use std::vec::Vec;
+
+    fn humidity_cycle(rh: [u8; 2], temp: [u8; 2]) -> [I2cTransaction; 3] {
+        [
+            I2cTransaction::write(0x40, vec![0xF5]),
+            I2cTransaction::read(0x40, vec![rh[0], rh[1], crc8(&rh)]),
+            I2cTransaction::write_read(0x40, vec![0xE0], temp.to_vec()),
+        ]
+    }
+
+    fn temperature_cycle(temp: [u8; 2]) -> [I2cTransaction; 2] {
+        [
+            I2cTransaction::write(0x40, vec![0xF3]),
+            I2cTransaction::read(0x40, vec![temp[0], temp[1], crc8(&temp)]),
+        ]
+    }
+
+    #[test]
+    fn humidity_is_measured_every_nth_read() {
+        let mut expectations = Vec::new();
+        expectations.extend(humidity_cycle([0x7C, 0x80], [0x66, 0x4C]));
+        expectations.extend(temperature_cycle([0x66, 0x50]));
+        expectations.extend(temperature_cycle([0x66, 0x54]));
+        expectations.extend(humidity_cycle([0x7D, 0x00], [0x66, 0x58]));
+        expectations.extend(temperature_cycle([0x66, 0x5C]));
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[
+            DelayTransaction::delay_ms(24),
+            DelayTransaction::delay_ms(12),
+            DelayTransaction::delay_ms(12),
+            DelayTransaction::delay_ms(24),
+            DelayTransaction::delay_ms(12),
+        ]);
+        let reads = Cell::new(0);
+        let mut sensor = Si70xx::new(i2c.clone()).with_after_read(|_| reads.set(reads.get() + 1));
+        let mut sequencer = SequencedReader::new(3);
+
+        let first = humidity_from_code(0x7C80, RoundingMode::Nearest);
+        let second = humidity_from_code(0x7D00, RoundingMode::Nearest);
+        let expected = [
+            (first, 0x664C),
+            (first, 0x6650),
+            (first, 0x6654),
+            (second, 0x6658),
+            (second, 0x665C),
+        ];
+        for (humidity, temp_code) in expected {
+            let m = sequencer.read(&mut sensor, &mut delay).unwrap();
+            let temperature = temperature_from_code(temp_code, RoundingMode::Nearest);
+            assert_eq!(m.humidity, humidity);
+            assert_eq!(m.temperature, temperature);
+            assert_eq!(sensor.last_temperature(), Some(temperature));
+        }
+        assert_eq!(reads.get(), 5);
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn error_starts_new_cycle() {
+        let mut expectations = Vec::new();
+        expectations.extend(humidity_cycle([0x7C, 0x80], [0x66, 0x4C]));
+        expectations.push(I2cTransaction::write(0x40, vec![0xF3]));
+        expectations.push(I2cTransaction::read(0x40, vec![0x66, 0x50, 0x00]));
+        expectations.extend(humidity_cycle([0x7C, 0x80], [0x66, 0x4C]));
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[
+            DelayTransaction::delay_ms(24),
+            DelayTransaction::delay_ms(12),
+            DelayTransaction::delay_ms(24),
+        ]);
+        let mut sensor = Si70xx::new(i2c.clone());
+        let mut sequencer = SequencedReader::new(3);
+        sequencer.read(&mut sensor, &mut delay).unwrap();
+        assert!(matches!(
+            sequencer.read(&mut sensor, &mut delay),
+            Err(Error::Crc(crate::Frame::Temperature))
+        ));
+        sequencer.read(&mut sensor, &mut delay).unwrap();
+        delay.done();
+        i2c.done();
+    }
+}
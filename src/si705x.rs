@@ -0,0 +1,520 @@
+//! Driver for Si7050/51/53/54/55 temperature-only sensors.
+//!
+//! Si705x uses the same protocol as Si70xx but has no relative humidity commands,
+//! therefore [`Si705x`] offers only the temperature part of the API.
+
+use core::marker::PhantomData;
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::{ErrorType, I2c};
+
+#[cfg(feature = "async")]
+use crate::Async;
+use crate::{
+    crc_valid, serial_number, temperature_from_code, Blocking, Command, Error, FirmwareRevision,
+    Frame, Resolution, RoundingMode, DEFAULT_TIMING_MARGIN_MS, READ_ELECTRONIC_ID_1,
+    READ_ELECTRONIC_ID_2, READ_FIRMWARE_REVISION, RESET_TIME_MS, USER_REGISTER1_RES_MASK,
+};
+
+/// Si705x I2C address.
+const SI705X_ADDR: u8 = 0x40;
+
+/// Blocking Si705x driver.
+pub type Si705x<I2C> = TemperatureDriver<I2C, Blocking>;
+
+/// Async Si705x driver.
+#[cfg(feature = "async")]
+pub type AsyncSi705x<I2C> = TemperatureDriver<I2C, Async>;
+
+/// Si705x temperature measurement resolution.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TemperatureResolution {
+    /// 14-bit, power-on default.
+    Bits14,
+    /// 13-bit.
+    Bits13,
+    /// 12-bit.
+    Bits12,
+    /// 11-bit.
+    Bits11,
+}
+
+impl TemperatureResolution {
+    /// Si70xx resolution sharing RES1 and RES0 bits and temperature conversion time.
+    fn equivalent(self) -> Resolution {
+        match self {
+            TemperatureResolution::Bits14 => Resolution::Rh12Temp14,
+            TemperatureResolution::Bits13 => Resolution::Rh10Temp13,
+            TemperatureResolution::Bits12 => Resolution::Rh8Temp12,
+            TemperatureResolution::Bits11 => Resolution::Rh11Temp11,
+        }
+    }
+}
+
+/// Si705x driver, use [`Si705x`] alias for the blocking API or `AsyncSi705x` for the async one.
+pub struct TemperatureDriver<I2C, M> {
+    i2c: I2C,
+    resolution: TemperatureResolution,
+    rounding: RoundingMode,
+    timing_margin_ms: u32,
+    crc: bool,
+    mode: PhantomData<M>,
+}
+
+impl<I2C, E, M> TemperatureDriver<I2C, M>
+where
+    I2C: ErrorType<Error = E>,
+{
+    /// Construct new Si705x sensor.
+    pub fn new(i2c: I2C) -> Self {
+        Self {
+            i2c,
+            resolution: TemperatureResolution::Bits14,
+            rounding: RoundingMode::Nearest,
+            timing_margin_ms: DEFAULT_TIMING_MARGIN_MS,
+            crc: true,
+            mode: PhantomData,
+        }
+    }
+
+    /// Sets how measurement codes are rounded, see [`RoundingMode`].
+    pub fn with_rounding_mode(mut self, rounding: RoundingMode) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// Sets the safety margin added to the conversion time when waiting with a delay.
+    ///
+    /// The default is 1 ms, see [`Si70xx::with_timing_margin_ms`](crate::Si70xx::with_timing_margin_ms).
+    pub fn with_timing_margin_ms(mut self, margin_ms: u32) -> Self {
+        self.timing_margin_ms = margin_ms;
+        self
+    }
+
+    /// Enables or disables checksum validation of temperature frames, enabled by default.
+    ///
+    /// Disabling it keeps the shorter 2-byte transfer and trusts the data, serial number and
+    /// firmware revision are not affected.
+    pub fn with_crc(mut self, enabled: bool) -> Self {
+        self.crc = enabled;
+        self
+    }
+
+    /// Returns the bus, for example to share it with other drivers.
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    /// Worst case conversion time at the configured resolution plus margin in milliseconds.
+    fn conversion_time_ms(&self) -> u32 {
+        self.resolution
+            .equivalent()
+            .temperature_conversion_time_ms()
+            + self.timing_margin_ms
+    }
+
+    /// Number of bytes read out from a temperature frame, the checksum is skipped if disabled.
+    fn frame_len(&self) -> usize {
+        if self.crc {
+            3
+        } else {
+            2
+        }
+    }
+
+    /// Validates a temperature frame and converts it to Celsius multiplied by 100.
+    ///
+    /// All-zero and all-one codes are never sent by the sensor and are rejected as
+    /// [`Error::InvalidData`], the all-zero frame even carries a valid checksum.
+    fn temperature(&self, response: [u8; 3]) -> Result<i16, Error<E>> {
+        if self.crc && !crc_valid(&response) {
+            return Err(Error::Crc(Frame::Temperature));
+        }
+        let bytes = [response[0], response[1]];
+        if bytes == [0x00, 0x00] || bytes == [0xFF, 0xFF] {
+            return Err(Error::InvalidData);
+        }
+        Ok(temperature_from_code(
+            u16::from_be_bytes(bytes),
+            self.rounding,
+        ))
+    }
+}
+
+impl<I2C, E> TemperatureDriver<I2C, Blocking>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Measures temperature without holding the bus and returns it in Celsius multiplied by 100.
+    ///
+    /// `delay` is used to wait for the conversion at the configured resolution.
+    pub fn read_temperature<D: DelayNs>(&mut self, delay: &mut D) -> Result<i16, Error<E>> {
+        self.i2c
+            .write(
+                SI705X_ADDR,
+                &[Command::MeasureTemperatureNoHoldMaster as u8],
+            )
+            .map_err(Error::I2c)?;
+        delay.delay_ms(self.conversion_time_ms());
+        let mut response = [0u8; 3];
+        let len = self.frame_len();
+        self.i2c
+            .read(SI705X_ADDR, &mut response[..len])
+            .map_err(Error::I2c)?;
+        self.temperature(response)
+    }
+
+    /// Measures temperature in Hold Master mode and returns it in Celsius multiplied by 100.
+    ///
+    /// The sensor stretches the clock until the conversion has finished.
+    pub fn read_temperature_hold(&mut self) -> Result<i16, Error<E>> {
+        let mut response = [0u8; 3];
+        let len = self.frame_len();
+        self.i2c
+            .write_read(
+                SI705X_ADDR,
+                &[Command::MeasureTemperatureHoldMaster as u8],
+                &mut response[..len],
+            )
+            .map_err(Error::I2c)?;
+        self.temperature(response)
+    }
+
+    /// Sets temperature measurement resolution, other bits of User Register 1 are kept.
+    pub fn set_resolution(&mut self, resolution: TemperatureResolution) -> Result<(), Error<E>> {
+        let mut reg = [0u8; 1];
+        self.i2c
+            .write_read(SI705X_ADDR, &[Command::ReadUserRegister1 as u8], &mut reg)
+            .map_err(Error::I2c)?;
+        let reg = (reg[0] & !USER_REGISTER1_RES_MASK) | resolution.equivalent().bits();
+        self.i2c
+            .write(SI705X_ADDR, &[Command::WriteUserRegister1 as u8, reg])
+            .map_err(Error::I2c)?;
+        self.resolution = resolution;
+        Ok(())
+    }
+
+    /// Resets the sensor and waits until it is ready using `delay`.
+    ///
+    /// Resolution returns to the power-on default of 14 bits.
+    pub fn reset<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Error<E>> {
+        self.i2c
+            .write(SI705X_ADDR, &[Command::Reset as u8])
+            .map_err(Error::I2c)?;
+        self.resolution = TemperatureResolution::Bits14;
+        delay.delay_ms(RESET_TIME_MS);
+        Ok(())
+    }
+
+    /// Reads the 64-bit electronic serial number, most significant byte first.
+    ///
+    /// [`Error::Crc`] with [`Frame::SerialNumber`] is returned if any checksum does not match.
+    pub fn read_serial_number(&mut self) -> Result<[u8; 8], Error<E>> {
+        let mut sna = [0u8; 8];
+        self.i2c
+            .write_read(SI705X_ADDR, &READ_ELECTRONIC_ID_1, &mut sna)
+            .map_err(Error::I2c)?;
+        let mut snb = [0u8; 6];
+        self.i2c
+            .write_read(SI705X_ADDR, &READ_ELECTRONIC_ID_2, &mut snb)
+            .map_err(Error::I2c)?;
+        serial_number(sna, snb)
+    }
+
+    /// Reads sensor firmware revision.
+    pub fn read_firmware_revision(&mut self) -> Result<FirmwareRevision, Error<E>> {
+        let mut response = [0u8; 1];
+        self.i2c
+            .write_read(SI705X_ADDR, &READ_FIRMWARE_REVISION, &mut response)
+            .map_err(Error::I2c)?;
+        Ok(FirmwareRevision::from(response[0]))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<I2C, E> TemperatureDriver<I2C, Async>
+where
+    I2C: embedded_hal_async::i2c::I2c<Error = E>,
+{
+    /// Measures temperature without holding the bus and returns it in Celsius multiplied by 100.
+    ///
+    /// `delay` is used to wait for the conversion at the configured resolution.
+    pub async fn read_temperature<D: embedded_hal_async::delay::DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<i16, Error<E>> {
+        self.i2c
+            .write(
+                SI705X_ADDR,
+                &[Command::MeasureTemperatureNoHoldMaster as u8],
+            )
+            .await
+            .map_err(Error::I2c)?;
+        delay.delay_ms(self.conversion_time_ms()).await;
+        let mut response = [0u8; 3];
+        let len = self.frame_len();
+        self.i2c
+            .read(SI705X_ADDR, &mut response[..len])
+            .await
+            .map_err(Error::I2c)?;
+        self.temperature(response)
+    }
+
+    /// Measures temperature in Hold Master mode and returns it in Celsius multiplied by 100.
+    ///
+    /// The sensor stretches the clock until the conversion has finished.
+    pub async fn read_temperature_hold(&mut self) -> Result<i16, Error<E>> {
+        let mut response = [0u8; 3];
+        let len = self.frame_len();
+        self.i2c
+            .write_read(
+                SI705X_ADDR,
+                &[Command::MeasureTemperatureHoldMaster as u8],
+                &mut response[..len],
+            )
+            .await
+            .map_err(Error::I2c)?;
+        self.temperature(response)
+    }
+
+    /// Sets temperature measurement resolution, other bits of User Register 1 are kept.
+    pub async fn set_resolution(
+        &mut self,
+        resolution: TemperatureResolution,
+    ) -> Result<(), Error<E>> {
+        let mut reg = [0u8; 1];
+        self.i2c
+            .write_read(SI705X_ADDR, &[Command::ReadUserRegister1 as u8], &mut reg)
+            .await
+            .map_err(Error::I2c)?;
+        let reg = (reg[0] & !USER_REGISTER1_RES_MASK) | resolution.equivalent().bits();
+        self.i2c
+            .write(SI705X_ADDR, &[Command::WriteUserRegister1 as u8, reg])
+            .await
+            .map_err(Error::I2c)?;
+        self.resolution = resolution;
+        Ok(())
+    }
+
+    /// Resets the sensor and waits until it is ready using `delay`.
+    ///
+    /// Resolution returns to the power-on default of 14 bits.
+    pub async fn reset<D: embedded_hal_async::delay::DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<(), Error<E>> {
+        self.i2c
+            .write(SI705X_ADDR, &[Command::Reset as u8])
+            .await
+            .map_err(Error::I2c)?;
+        self.resolution = TemperatureResolution::Bits14;
+        delay.delay_ms(RESET_TIME_MS).await;
+        Ok(())
+    }
+
+    /// Reads the 64-bit electronic serial number, most significant byte first.
+    ///
+    /// [`Error::Crc`] with [`Frame::SerialNumber`] is returned if any checksum does not match.
+    pub async fn read_serial_number(&mut self) -> Result<[u8; 8], Error<E>> {
+        let mut sna = [0u8; 8];
+        self.i2c
+            .write_read(SI705X_ADDR, &READ_ELECTRONIC_ID_1, &mut sna)
+            .await
+            .map_err(Error::I2c)?;
+        let mut snb = [0u8; 6];
+        self.i2c
+            .write_read(SI705X_ADDR, &READ_ELECTRONIC_ID_2, &mut snb)
+            .await
+            .map_err(Error::I2c)?;
+        serial_number(sna, snb)
+    }
+
+    /// Reads sensor firmware revision.
+    pub async fn read_firmware_revision(&mut self) -> Result<FirmwareRevision, Error<E>> {
+        let mut response = [0u8; 1];
+        self.i2c
+            .write_read(SI705X_ADDR, &READ_FIRMWARE_REVISION, &mut response)
+            .await
+            .map_err(Error::I2c)?;
+        Ok(FirmwareRevision::from(response[0]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::crc8;
+    use embedded_hal_mock::eh1::delay::{CheckedDelay, Transaction as DelayTransaction};
+    use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+    use std::vec;
+
+    #[test]
+    fn read_temperature_waits_for_resolution() {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]),
+            I2cTransaction::write(0x40, vec![0xE6, 0xBB]),
+            I2cTransaction::write(0x40, vec![0xF3]),
+            I2cTransaction::read(0x40, vec![0x66, 0x4C, crc8(&[0x66, 0x4C])]),
+            I2cTransaction::write_read(0x40, vec![0xE3], vec![0x66, 0x4C, 0x00]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[DelayTransaction::delay_ms(4)]);
+        let mut sensor = Si705x::new(i2c.clone());
+        sensor
+            .set_resolution(TemperatureResolution::Bits11)
+            .unwrap();
+        assert_eq!(
+            sensor.read_temperature(&mut delay).unwrap(),
+            temperature_from_code(0x664C, RoundingMode::Nearest)
+        );
+        assert!(matches!(
+            sensor.read_temperature_hold(),
+            Err(Error::Crc(Frame::Temperature))
+        ));
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn hold_read_validates_checksum() {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0xE3], vec![0x66, 0x4C, crc8(&[0x66, 0x4C])]),
+            I2cTransaction::write(0x40, vec![0xF3]),
+            I2cTransaction::read(0x40, vec![0x66, 0x4C, crc8(&[0x66, 0x4C]) ^ 0x01]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[DelayTransaction::delay_ms(12)]);
+        let mut sensor = Si705x::new(i2c.clone()).with_rounding_mode(RoundingMode::Floor);
+        assert_eq!(
+            sensor.read_temperature_hold().unwrap(),
+            temperature_from_code(0x664C, RoundingMode::Floor)
+        );
+        assert!(matches!(
+            sensor.read_temperature(&mut delay),
+            Err(Error::Crc(Frame::Temperature))
+        ));
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn rejects_all_zero_and_all_one_frames() {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0xE3], vec![0x00, 0x00, 0x00]),
+            I2cTransaction::write_read(0x40, vec![0xE3], vec![0xFF, 0xFF, crc8(&[0xFF, 0xFF])]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut sensor = Si705x::new(i2c.clone());
+        assert!(matches!(
+            sensor.read_temperature_hold(),
+            Err(Error::InvalidData)
+        ));
+        assert!(matches!(
+            sensor.read_temperature_hold(),
+            Err(Error::InvalidData)
+        ));
+        i2c.done();
+    }
+
+    #[test]
+    fn timing_margin_and_crc_opt_out() {
+        let expectations = [
+            I2cTransaction::write(0x40, vec![0xF3]),
+            I2cTransaction::read(0x40, vec![0x66, 0x4C]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[DelayTransaction::delay_ms(16)]);
+        let mut sensor = Si705x::new(i2c.clone())
+            .with_timing_margin_ms(5)
+            .with_crc(false);
+        assert_eq!(
+            sensor.read_temperature(&mut delay).unwrap(),
+            temperature_from_code(0x664C, RoundingMode::Nearest)
+        );
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn set_resolution_keeps_other_bits() {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x7F]),
+            I2cTransaction::write(0x40, vec![0xE6, 0xFE]),
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0xFE]),
+            I2cTransaction::write(0x40, vec![0xE6, 0x7F]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut sensor = Si705x::new(i2c.clone());
+        sensor
+            .set_resolution(TemperatureResolution::Bits13)
+            .unwrap();
+        sensor
+            .set_resolution(TemperatureResolution::Bits12)
+            .unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn reset_restores_14_bit_resolution() {
+        let temperature = [0x66, 0x4C, crc8(&[0x66, 0x4C])];
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0xE7], vec![0x3A]),
+            I2cTransaction::write(0x40, vec![0xE6, 0xBA]),
+            I2cTransaction::write(0x40, vec![0xF3]),
+            I2cTransaction::read(0x40, temperature.to_vec()),
+            I2cTransaction::write(0x40, vec![0xFE]),
+            I2cTransaction::write(0x40, vec![0xF3]),
+            I2cTransaction::read(0x40, temperature.to_vec()),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut delay = CheckedDelay::new(&[
+            DelayTransaction::delay_ms(8),
+            DelayTransaction::delay_ms(RESET_TIME_MS),
+            DelayTransaction::delay_ms(12),
+        ]);
+        let mut sensor = Si705x::new(i2c.clone());
+        sensor
+            .set_resolution(TemperatureResolution::Bits13)
+            .unwrap();
+        sensor.read_temperature(&mut delay).unwrap();
+        sensor.reset(&mut delay).unwrap();
+        sensor.read_temperature(&mut delay).unwrap();
+        delay.done();
+        i2c.done();
+    }
+
+    #[test]
+    fn reads_serial_number_and_firmware_revision() {
+        let sna = [0x12, 0x34, 0x56, 0x78];
+        let snb = [0x33, 0xFF, 0xAB, 0xCD];
+        let first = sna.iter().flat_map(|b| [*b, crc8(&[*b])]).collect();
+        let second = vec![
+            snb[0],
+            snb[1],
+            crc8(&snb[..2]),
+            snb[2],
+            snb[3],
+            crc8(&snb[2..]),
+        ];
+        let expectations = [
+            I2cTransaction::write_read(0x40, vec![0xFA, 0x0F], first),
+            I2cTransaction::write_read(0x40, vec![0xFC, 0xC9], second),
+            I2cTransaction::write_read(0x40, vec![0x84, 0xB8], vec![0xFF]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut sensor = Si705x::new(i2c.clone());
+        let serial_number = sensor.read_serial_number().unwrap();
+        assert_eq!(
+            serial_number,
+            [0x12, 0x34, 0x56, 0x78, 0x33, 0xFF, 0xAB, 0xCD]
+        );
+        assert_eq!(crate::Device::from(serial_number[4]), crate::Device::Si7051);
+        assert_eq!(
+            sensor.read_firmware_revision().unwrap(),
+            FirmwareRevision::V1_0
+        );
+        i2c.done();
+    }
+}